@@ -1,34 +1,5 @@
-const x = [
-  [],
-  [0],
-  [0, 0],
-  [0, 0, 0.5],
-  [0, 0.5, 0, 0.5]
-]
-
-const y = [
-  [],
-  [0],
-  [0, 0.5],
-  [0, 0.5, 0.5],
-  [0, 0, 0.5, 0.5]
-]
-
-const width = [
-  [],
-  [1],
-  [1, 1],
-  [1, 0.5, 0.5],
-  [0.5, 0.5, 0.5, 0.5]
-]
-
-const height = [
-  [],
-  [1],
-  [0.5, 0.5],
-  [0.5, 0.5, 0.5],
-  [0.5, 0.5, 0.5, 0.5]
-]
+// Geometry of each game instance's window relative to its target output, by gamescope PID; generated by PartyDeck
+// %WINDOWS%
 
 function getGamescopeClients() {
   var allClients = workspace.windowList();
@@ -45,17 +16,6 @@ function getGamescopeClients() {
   return gamescopeClients;
 }
 
-function numGamescopeClientsInOutput(output) {
-  var gamescopeClients = getGamescopeClients();
-  var count = 0;
-  for (var i = 0; i < gamescopeClients.length; i++) {
-    if (gamescopeClients[i].output == output) {
-      count++;
-    }
-  }
-  return count;
-}
-
 function gamescopeAboveBelow() {
   var gamescopeClients = getGamescopeClients();
   for (var i = 0; i < gamescopeClients.length; i++) {
@@ -70,32 +30,43 @@ function gamescopeAboveBelow() {
   }
 }
 
+// The output at the window's target position, or the one it's on if there's no such output
+function targetOutput(client, geometry) {
+  if (geometry.output !== null) {
+    var screens = workspace.screens;
+    for (var i = 0; i < screens.length; i++) {
+      if (
+        screens[i].geometry.x == geometry.output.x &&
+        screens[i].geometry.y == geometry.output.y
+      ) {
+        return screens[i];
+      }
+    }
+  }
+  return client.output;
+}
+
 function gamescopeSplitscreen() {
   var gamescopeClients = getGamescopeClients();
 
-  var screenMap = new Map();
-  var screens = workspace.screens;
-  for (var j = 0; j < screens.length; j++) {
-    screenMap.set(screens[j], 0);
-  }
-
   for (var i = 0; i < gamescopeClients.length; i++) {
-    var monitor = gamescopeClients[i].output;
+    var geometry = windows[gamescopeClients[i].pid];
+    if (geometry === undefined) {
+      continue;
+    }
+
+    var monitor = targetOutput(gamescopeClients[i], geometry);
     var monitorX = monitor.geometry.x;
     var monitorY = monitor.geometry.y;
     var monitorWidth = monitor.geometry.width;
     var monitorHeight = monitor.geometry.height;
 
-    var playerCount = numGamescopeClientsInOutput(monitor);
-    var playerIndex = screenMap.get(monitor);
-    screenMap.set(monitor, playerIndex + 1);
-
     gamescopeClients[i].noBorder = true;
     gamescopeClients[i].frameGeometry = {
-      x: monitorX + x[playerCount][playerIndex] * monitorWidth,
-      y: monitorY + y[playerCount][playerIndex] * monitorHeight,
-      width: monitorWidth * width[playerCount][playerIndex],
-      height: monitorHeight * height[playerCount][playerIndex],
+      x: monitorX + geometry.x * monitorWidth,
+      y: monitorY + geometry.y * monitorHeight,
+      width: monitorWidth * geometry.width,
+      height: monitorHeight * geometry.height,
     };
   }
   gamescopeAboveBelow();
@@ -104,3 +75,6 @@ function gamescopeSplitscreen() {
 workspace.windowAdded.connect(gamescopeSplitscreen);
 workspace.windowRemoved.connect(gamescopeSplitscreen);
 workspace.windowActivated.connect(gamescopeAboveBelow);
+
+// The instances are already running when the script is loaded
+gamescopeSplitscreen();
@@ -4,12 +4,22 @@ use super::config::*;
 use crate::handler::*;
 use crate::input::*;
 use crate::instance::*;
+use crate::keymap::*;
 use crate::launch::*;
-use crate::monitor::Monitor;
+use crate::layout::*;
+use crate::logs::scan_log_files;
+use crate::monitor::{Monitor, get_monitors_sdl};
+use crate::paths::PATH_PARTY;
+use crate::presets::*;
 use crate::profiles::*;
+use crate::remap::Remap;
+use crate::session::*;
+use crate::snapshots::*;
 use crate::util::*;
 
+use dialog::DialogBox;
 use eframe::egui::{self, Key};
+use evdev::KeyCode;
 
 #[derive(Eq, PartialEq)]
 pub enum MenuPage {
@@ -19,9 +29,11 @@ pub enum MenuPage {
     EditHandler,
     Game,
     Instances,
+    Session,
+    Logs,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Clone, Copy)]
 pub enum SettingsPage {
     General,
     Proton,
@@ -38,15 +50,37 @@ pub struct PartyApp {
 
     pub monitors: Vec<Monitor>,
     pub input_devices: Vec<InputDevice>,
+    pub device_watcher: DeviceWatcher,
+    // The device whose row the mouse is over, so it only rumbles once per hover
+    pub hovered_device: Option<DeviceId>,
+    // Player numbers last shown on each device's LEDs
+    pub player_leds: Vec<(DeviceId, usize)>,
     pub instances: Vec<Instance>,
     pub instance_add_dev: Option<usize>,
     pub profiles: Vec<String>,
+    pub device_profiles: Vec<DeviceProfile>,
+    // Profile whose button remapping is being edited on the profiles page
+    pub profile_remap: Option<(String, Remap)>,
+    // Profile whose save snapshots are listed on the profiles page
+    pub profile_snapshots: Option<(String, Vec<SaveSnapshot>)>,
+    pub keymaps: Vec<Keymap>,
+    pub presets: Vec<SessionPreset>,
+    pub layouts: Vec<CustomLayout>,
+    // The custom layout selected in the settings, loaded whenever the selection changes
+    pub selected_layout: Option<CustomLayout>,
 
     pub handlers: Vec<Handler>,
     pub selected_handler: usize,
     pub handler_edit: Option<Handler>,
     pub handler_lite: Option<Handler>,
 
+    pub session: Option<SharedSession>,
+
+    pub log_files: Vec<std::path::PathBuf>,
+    pub log_selected: usize,
+    pub log_text: String,
+    pub log_read_at: Option<std::time::Instant>,
+
     pub loading_msg: Option<String>,
     pub loading_since: Option<std::time::Instant>,
     #[allow(dead_code)]
@@ -62,6 +96,7 @@ macro_rules! cur_handler {
 impl PartyApp {
     pub fn new(monitors: Vec<Monitor>, handler_lite: Option<Handler>) -> Self {
         let options = load_cfg();
+        let selected_layout = load_layout(&options.layout);
         let input_devices = scan_input_devices(&options.pad_filter_type);
         let handlers = match handler_lite {
             Some(_) => Vec::new(),
@@ -81,13 +116,28 @@ impl PartyApp {
             infotext: String::new(),
             monitors,
             input_devices,
+            device_watcher: DeviceWatcher::new(),
+            hovered_device: None,
+            player_leds: Vec::new(),
             instances: Vec::new(),
             instance_add_dev: None,
             handlers,
             selected_handler: 0,
             handler_edit: None,
             handler_lite,
+            session: None,
+            log_files: Vec::new(),
+            log_selected: 0,
+            log_text: String::new(),
+            log_read_at: None,
             profiles: scan_profiles(false),
+            device_profiles: load_device_profiles(),
+            profile_remap: None,
+            profile_snapshots: None,
+            keymaps: load_keymaps(),
+            presets: load_presets(),
+            layouts: scan_layouts(),
+            selected_layout,
             loading_msg: None,
             loading_since: None,
             task: None,
@@ -113,6 +163,14 @@ impl eframe::App for PartyApp {
     }
 
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let changed = self.device_watcher.changed_paths();
+        if !changed.is_empty() {
+            update_input_devices(&mut self.input_devices, &changed, &self.options.pad_filter_type);
+            // Replugged devices need their LEDs set again
+            self.player_leds.clear();
+        }
+        self.update_player_leds();
+
         egui::TopBottomPanel::top("menu_nav_panel").show(ctx, |ui| {
             if self.task.is_some() {
                 ui.disable();
@@ -159,9 +217,23 @@ impl eframe::App for PartyApp {
                 MenuPage::EditHandler => self.display_page_edit_handler(ui),
                 MenuPage::Game => self.display_page_game(ui),
                 MenuPage::Instances => self.display_page_instances(ui),
+                MenuPage::Session => self.display_page_session(ui),
+                MenuPage::Logs => self.display_page_logs(ui),
             }
         });
 
+        if let Some(session) = &self.session
+            && session.lock().unwrap().finished
+        {
+            self.session = None;
+            // Guests may have been kept as profiles, and snapshots taken at launch
+            self.profiles = scan_profiles(false);
+            self.refresh_profile_snapshots();
+            if self.cur_page == MenuPage::Session {
+                self.cur_page = MenuPage::Home;
+            }
+        }
+
         if let Some(handle) = self.task.take() {
             if handle.is_finished() {
                 let _ = handle.join();
@@ -217,6 +289,8 @@ impl PartyApp {
 
     fn handle_gamepad_gui(&mut self, raw_input: &mut egui::RawInput) {
         let mut key: Option<egui::Key> = None;
+        // Forward for RB, back for LB
+        let mut switch_page: Option<bool> = None;
         for pad in &mut self.input_devices {
             if !pad.enabled() {
                 continue;
@@ -236,6 +310,8 @@ impl PartyApp {
                 }
                 Some(PadButton::YBtn) => self.cur_page = MenuPage::Settings,
                 Some(PadButton::SelectBtn) => key = Some(Key::Tab),
+                Some(PadButton::LBBtn) => switch_page = Some(false),
+                Some(PadButton::RBBtn) => switch_page = Some(true),
                 Some(PadButton::StartBtn) => {
                     if self.cur_page == MenuPage::Game {
                         self.instances.clear();
@@ -253,6 +329,10 @@ impl PartyApp {
             }
         }
 
+        if let Some(forward) = switch_page {
+            self.switch_page(forward);
+        }
+
         if let Some(key) = key {
             raw_input.events.push(egui::Event::Key {
                 key,
@@ -264,6 +344,69 @@ impl PartyApp {
         }
     }
 
+    // Steps through the pages in the top bar, going through the settings tabs on the way
+    fn switch_page(&mut self, forward: bool) {
+        if self.cur_page == MenuPage::Settings {
+            let tabs = [
+                SettingsPage::General,
+                SettingsPage::Proton,
+                SettingsPage::Gamescope,
+            ];
+            let tab = tabs.iter().position(|t| *t == self.settings_page).unwrap_or(0);
+            let next = match forward {
+                true => tab.checked_add(1).filter(|t| *t < tabs.len()),
+                false => tab.checked_sub(1),
+            };
+            if let Some(next) = next {
+                self.settings_page = tabs[next];
+                return;
+            }
+        }
+
+        let mut pages = vec![
+            match self.is_lite() {
+                true => MenuPage::Instances,
+                false => MenuPage::Home,
+            },
+            MenuPage::Settings,
+            MenuPage::Profiles,
+        ];
+        if self.session.is_some() {
+            pages.push(MenuPage::Session);
+        }
+        pages.push(MenuPage::Logs);
+
+        // Pages outside the top bar, like the game and instance pages, aren't left this way
+        let Some(page) = pages.iter().position(|p| *p == self.cur_page) else {
+            return;
+        };
+        let next = match forward {
+            true => (page + 1) % pages.len(),
+            false => (page + pages.len() - 1) % pages.len(),
+        };
+        if pages[next] == MenuPage::Settings {
+            self.settings_page = match forward {
+                true => SettingsPage::General,
+                false => SettingsPage::Gamescope,
+            };
+        }
+        self.go_to_page(pages.swap_remove(next));
+    }
+
+    // Opens a page from the top bar, loading what it shows
+    pub fn go_to_page(&mut self, page: MenuPage) {
+        match page {
+            MenuPage::Profiles => self.profiles = scan_profiles(false),
+            MenuPage::Logs => {
+                self.log_files = scan_log_files();
+                self.log_selected = 0;
+                self.log_read_at = None;
+            }
+            _ => {}
+        }
+        self.cur_page = page;
+    }
+
     fn handle_devices_instance_menu(&mut self) {
         let mut i = 0;
         while i < self.input_devices.len() {
@@ -271,22 +414,28 @@ impl PartyApp {
                 i += 1;
                 continue;
             }
-            match self.input_devices[i].poll() {
+            let id = self.input_devices[i].id().clone();
+            let btn = self.input_devices[i].poll();
+            if self.handle_keyboard_gamepad(i, &id) {
+                i += 1;
+                continue;
+            }
+            match btn {
                 Some(PadButton::ABtn) | Some(PadButton::ZKey) | Some(PadButton::RightClick) => {
-                    if self.input_devices[i].device_type() != DeviceType::Gamepad
+                    if !self.input_devices[i].device_type().is_controller()
                         && !self.options.kbm_support
                     {
                         continue;
                     }
                     if !self.options.allow_multiple_instances_on_same_device
-                        && self.is_device_in_any_instance(i)
+                        && self.is_device_in_any_instance(&id)
                     {
                         continue;
                     }
                     // Prevent same keyboard/mouse device in multiple instances due to current custom gamescope limitations
                     // TODO: Remove this when custom gamescope supports the same keyboard/mouse device for multiple instances
-                    if self.input_devices[i].device_type() != DeviceType::Gamepad
-                        && self.is_device_in_any_instance(i)
+                    if !self.input_devices[i].device_type().is_controller()
+                        && self.is_device_in_any_instance(&id)
                     {
                         continue;
                     }
@@ -294,45 +443,50 @@ impl PartyApp {
                     match self.instance_add_dev {
                         Some(inst) => {
                             // Add the device in the instance only if it's not already there
-                            if !self.is_device_in_instance(inst, i) {
+                            if !self.is_device_in_instance(inst, &id) {
                                 self.instance_add_dev = None;
-                                self.instances[inst].devices.push(i);
+                                self.instances[inst].devices.push(id.clone());
+                                self.input_devices[i].rumble();
                             } else {
                                 continue;
                             }
                         }
                         None => {
+                            let profselection = self.remembered_profselection(&id);
                             self.instances.push(Instance {
-                                devices: vec![i],
+                                devices: vec![id.clone()],
+                                keymap: None,
                                 profname: String::new(),
-                                profselection: 0,
+                                profselection,
                                 monitor: 0,
                                 width: 0,
                                 height: 0,
+                                rect: Rect::FULL,
                             });
+                            self.input_devices[i].rumble();
                         }
                     }
                 }
                 Some(PadButton::BBtn) | Some(PadButton::XKey) => {
                     if self.instance_add_dev != None {
                         self.instance_add_dev = None;
-                    } else if self.is_device_in_any_instance(i) {
-                        self.remove_device(i);
+                    } else if self.is_device_in_any_instance(&id) {
+                        self.remove_device(&id);
                     } else if self.instances.len() < 1 {
                         self.cur_page = MenuPage::Game;
                     }
                 }
                 Some(PadButton::YBtn) | Some(PadButton::AKey) => {
                     if self.instance_add_dev == None {
-                        if let Some((instance, _)) = self.find_device_in_instance(i) {
+                        if let Some((instance, _)) = self.find_device_in_instance(&id) {
                             self.instance_add_dev = Some(instance);
                         }
                     }
                 }
-                Some(PadButton::StartBtn) => {
-                    if self.instances.len() > 0 && self.is_device_in_any_instance(i) {
-                        self.prepare_game_launch();
-                    }
+                Some(PadButton::StartBtn)
+                    if !self.instances.is_empty() && self.is_device_in_any_instance(&id) =>
+                {
+                    self.prepare_game_launch();
                 }
                 _ => {}
             }
@@ -340,26 +494,140 @@ impl PartyApp {
         }
     }
 
-    fn is_device_in_any_instance(&self, dev: usize) -> bool {
+    // Keyboard halves join with their keymap's A key and leave with its B key. Returns true when
+    // the key press was meant for a keyboard gamepad, so it isn't handled as menu input.
+    fn handle_keyboard_gamepad(&mut self, i: usize, id: &DeviceId) -> bool {
+        if !self.options.virtual_gamepads
+            || !self.options.keyboard_gamepads
+            || self.input_devices[i].device_type() != DeviceType::Keyboard
+        {
+            return false;
+        }
+        let Some(key) = self.input_devices[i].pressed_key() else {
+            return false;
+        };
+
+        for (k, keymap) in self.keymaps.iter().enumerate() {
+            if keymap.key_for(KeyCode::BTN_SOUTH) == Some(key) {
+                // Already used as a keyboard, or this half is already playing
+                if self.instances.iter().any(|instance| {
+                    instance.devices.contains(id)
+                        && (instance.keymap.is_none() || instance.keymap == Some(k))
+                }) {
+                    return true;
+                }
+                match self.instance_add_dev {
+                    Some(inst) => {
+                        if self.instances[inst].keymap.is_none()
+                            && !self.is_device_in_instance(inst, id)
+                        {
+                            self.instance_add_dev = None;
+                            self.instances[inst].devices.push(id.clone());
+                            self.instances[inst].keymap = Some(k);
+                        }
+                    }
+                    None => {
+                        let profselection = self.remembered_profselection(id);
+                        self.instances.push(Instance {
+                            devices: vec![id.clone()],
+                            keymap: Some(k),
+                            profname: String::new(),
+                            profselection,
+                            monitor: 0,
+                            width: 0,
+                            height: 0,
+                            rect: Rect::FULL,
+                        });
+                    }
+                }
+                return true;
+            }
+            if keymap.key_for(KeyCode::BTN_EAST) == Some(key)
+                && let Some(inst) = self.instances.iter().position(|instance| {
+                    instance.keymap == Some(k) && instance.devices.contains(id)
+                })
+            {
+                self.remove_device_instance(inst, id);
+                return true;
+            }
+        }
+
+        // Other keys of a keyboard gamepad are its sticks and buttons
+        self.instances
+            .iter()
+            .any(|instance| instance.keymap.is_some() && instance.devices.contains(id))
+    }
+
+    // Rumbles a device when the mouse moves onto its row
+    pub fn rumble_on_hover(&mut self, hovered: Option<DeviceId>) {
+        if hovered != self.hovered_device
+            && let Some(id) = &hovered
+            && let Some(d) = find_device(&self.input_devices, id)
+        {
+            self.input_devices[d].rumble();
+        }
+        self.hovered_device = hovered;
+    }
+
+    // Shows each device's player number on its LEDs whenever the instances change
+    fn update_player_leds(&mut self) {
+        let mut leds: Vec<(DeviceId, usize)> = Vec::new();
+        for (i, instance) in self.instances.iter().enumerate() {
+            for dev in &instance.devices {
+                leds.push((dev.clone(), i + 1));
+            }
+        }
+        if leds == self.player_leds {
+            return;
+        }
+        for pad in &self.input_devices {
+            let player = leds
+                .iter()
+                .find(|(id, _)| id == pad.id())
+                .map_or(0, |(_, player)| *player);
+            let before = self
+                .player_leds
+                .iter()
+                .find(|(id, _)| id == pad.id())
+                .map_or(0, |(_, player)| *player);
+            if player != before {
+                pad.set_player_leds(player);
+            }
+        }
+        self.player_leds = leds;
+    }
+
+    // The profile the device was last used with, unless it's gone or another player already has it
+    fn remembered_profselection(&self, dev: &DeviceId) -> usize {
+        let Some(profile) = remembered_profile(&self.device_profiles, dev) else {
+            return 0;
+        };
+        match self.profiles.iter().position(|p| p == profile) {
+            Some(sel) if sel != 0 && !self.instances.iter().any(|i| i.profselection == sel) => sel,
+            _ => 0,
+        }
+    }
+
+    fn is_device_in_any_instance(&self, dev: &DeviceId) -> bool {
         for instance in &self.instances {
-            if instance.devices.contains(&dev) {
+            if instance.devices.contains(dev) {
                 return true;
             }
         }
         false
     }
 
-    fn is_device_in_instance(&self, instance_index: usize, dev: usize) -> bool {
-        if self.instances[instance_index].devices.contains(&dev) {
+    fn is_device_in_instance(&self, instance_index: usize, dev: &DeviceId) -> bool {
+        if self.instances[instance_index].devices.contains(dev) {
             return true;
         }
         false
     }
 
-    fn find_device_in_instance(&mut self, dev: usize) -> Option<(usize, usize)> {
+    fn find_device_in_instance(&mut self, dev: &DeviceId) -> Option<(usize, usize)> {
         for (i, instance) in self.instances.iter().enumerate() {
             for (d, device) in instance.devices.iter().enumerate() {
-                if device == &dev {
+                if device == dev {
                     return Some((i, d));
                 }
             }
@@ -367,10 +635,10 @@ impl PartyApp {
         None
     }
 
-    fn find_device_in_instance_from_end(&mut self, dev: usize) -> Option<(usize, usize)> {
+    fn find_device_in_instance_from_end(&mut self, dev: &DeviceId) -> Option<(usize, usize)> {
         for (i, instance) in self.instances.iter().enumerate().rev() {
             for (d, device) in instance.devices.iter().enumerate() {
-                if device == &dev {
+                if device == dev {
                     return Some((i, d));
                 }
             }
@@ -378,23 +646,25 @@ impl PartyApp {
         None
     }
 
-    pub fn remove_device(&mut self, dev: usize) {
+    pub fn remove_device(&mut self, dev: &DeviceId) {
         if let Some((instance_index, device_index)) = self.find_device_in_instance_from_end(dev) {
             self.instances[instance_index].devices.remove(device_index);
+            self.forget_keymap(instance_index, dev);
             if self.instances[instance_index].devices.is_empty() {
                 self.instances.remove(instance_index);
             }
         }
     }
 
-    pub fn remove_device_instance(&mut self, instance_index: usize, dev: usize) {
+    pub fn remove_device_instance(&mut self, instance_index: usize, dev: &DeviceId) {
         let device_index = self.instances[instance_index]
             .devices
             .iter()
-            .position(|device| device == &dev);
+            .position(|device| device == dev);
 
         if let Some(d) = device_index {
             self.instances[instance_index].devices.remove(d);
+            self.forget_keymap(instance_index, dev);
 
             if self.instances[instance_index].devices.is_empty() {
                 self.instances.remove(instance_index);
@@ -402,68 +672,234 @@ impl PartyApp {
         }
     }
 
-    pub fn prepare_game_launch(&mut self) {
-        if self.options.gamescope_sdl_backend {
-            set_instance_resolutions_multimonitor(
-                &mut self.instances,
-                &self.monitors,
-                &self.options,
-            );
-        } else {
-            set_instance_resolutions(&mut self.instances, &self.monitors[0], &self.options);
+    // An instance stops using a keymap once its keyboard is removed
+    fn forget_keymap(&mut self, instance_index: usize, dev: &DeviceId) {
+        let is_keyboard = match find_device(&self.input_devices, dev) {
+            Some(d) => self.input_devices[d].device_type() == DeviceType::Keyboard,
+            None => true,
+        };
+        if is_keyboard {
+            self.instances[instance_index].keymap = None;
         }
-        set_instance_names(&mut self.instances, &self.profiles);
+    }
 
-        let handler = if let Some(h) = self.handler_lite.clone() {
+    fn launch_handler(&self) -> Handler {
+        if let Some(h) = self.handler_lite.clone() {
             h
         } else {
             cur_handler!(self).to_owned()
+        }
+    }
+
+    // Writes the launch plan for the current instances to a JSON file and opens it, without launching anything
+    pub fn show_launch_plan(&mut self) {
+        let mut instances = self.instances.clone();
+        prepare_instances(&mut instances, &self.monitors, &self.profiles, &self.options);
+
+        let handler = self.launch_handler();
+        let dev_infos: Vec<DeviceInfo> = self.input_devices.iter().map(|p| p.info()).collect();
+        let path = PATH_PARTY.join("launch_plan.json");
+
+        let plan = match launch_plan(&handler, &dev_infos, &instances, &self.options, true) {
+            Ok(plan) => plan,
+            Err(err) => {
+                msg("Error", &format!("Couldn't create launch plan: {}", err));
+                return;
+            }
+        };
+        let written = plan
+            .to_json()
+            .map_err(|e| e.to_string())
+            .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+        if let Err(err) = written {
+            msg("Error", &format!("Couldn't save launch plan: {}", err));
+            return;
+        }
+
+        println!("[partydeck] Launch plan saved to {}", path.display());
+        if std::process::Command::new("xdg-open").arg(&path).status().is_err() {
+            msg("Error", "Couldn't open launch plan!");
+        }
+    }
+
+    pub fn rename_profile(&mut self, profile: &str) {
+        let Some(name) = dialog::Input::new("Enter new name (must be alphanumeric):")
+            .title("Rename Profile")
+            .default(profile)
+            .show()
+            .expect("Could not display dialog box")
+        else {
+            return;
+        };
+        if name == profile {
+            return;
+        }
+        if !valid_profile_name(&name) {
+            msg("Error", "Invalid name");
+            return;
+        }
+        if let Err(err) = rename_profile(profile, &name) {
+            msg("Error", &format!("Couldn't rename profile: {err}"));
+            return;
+        }
+
+        if let Err(err) = rename_device_profiles(&mut self.device_profiles, profile, &name) {
+            println!("[partydeck] Couldn't save device profiles: {}", err);
+        }
+        if let Err(err) = rename_preset_profiles(&mut self.presets, profile, &name) {
+            println!("[partydeck] Couldn't save session presets: {}", err);
+        }
+        if let Some((p, _)) = &mut self.profile_remap
+            && p == profile
+        {
+            *p = name.clone();
+        }
+        if let Some((p, _)) = &mut self.profile_snapshots
+            && p == profile
+        {
+            *p = name;
+        }
+        self.profiles = scan_profiles(false);
+        self.refresh_profile_snapshots();
+    }
+
+    pub fn delete_profile(&mut self, profile: &str) {
+        if let Err(err) = delete_profile(profile) {
+            msg("Error", &format!("Couldn't delete profile: {err}"));
+            self.profiles = scan_profiles(false);
+            return;
+        }
+
+        if let Err(err) = forget_device_profiles(&mut self.device_profiles, profile) {
+            println!("[partydeck] Couldn't save device profiles: {}", err);
+        }
+        if let Err(err) = forget_preset_profiles(&mut self.presets, profile) {
+            println!("[partydeck] Couldn't save session presets: {}", err);
+        }
+        if self.profile_remap.as_ref().is_some_and(|(p, _)| p == profile) {
+            self.profile_remap = None;
+        }
+        if self.profile_snapshots.as_ref().is_some_and(|(p, _)| p == profile) {
+            self.profile_snapshots = None;
+        }
+        self.profiles = scan_profiles(false);
+    }
+
+    pub fn refresh_profile_snapshots(&mut self) {
+        if let Some((profile, snapshots)) = &mut self.profile_snapshots {
+            *snapshots = scan_snapshots(profile);
+        }
+    }
+
+    // Sets up the instances page for a new session, optionally with a preset's players
+    pub fn open_instances_page(&mut self, preset: Option<usize>) {
+        self.input_devices = scan_input_devices(&self.options.pad_filter_type);
+        self.monitors = get_monitors_sdl();
+        self.profiles = scan_profiles(true);
+        self.instance_add_dev = None;
+        self.instances = match preset {
+            Some(p) => self.presets[p].to_instances(&self.profiles, self.monitors.len()),
+            None => Vec::new(),
         };
+        if let Some(p) = preset
+            && self.presets[p].layout != self.options.layout
+            && (self.presets[p].layout.is_empty()
+                || self.layouts.iter().any(|l| l.id == self.presets[p].layout))
+        {
+            self.options.layout = self.presets[p].layout.clone();
+            self.reload_selected_layout();
+        }
+        self.cur_page = MenuPage::Instances;
+    }
+
+    pub fn reload_selected_layout(&mut self) {
+        self.selected_layout = load_layout(&self.options.layout);
+    }
+
+    pub fn save_preset(&mut self, name: &str) {
+        let preset = SessionPreset::from_instances(
+            name,
+            self.launch_handler().handler_dir_name(),
+            &self.instances,
+            &self.profiles,
+            &self.options,
+        );
+        if let Err(err) = store_preset(&mut self.presets, preset) {
+            msg("Error", &format!("Couldn't save preset: {err}"));
+        }
+    }
+
+    pub fn prepare_game_launch(&mut self) {
+        if self.session.is_some() {
+            msg("Error", "A session is already running!");
+            return;
+        }
+        for (i, instance) in self.instances.iter().enumerate() {
+            if let Some(dev) = instance
+                .devices
+                .iter()
+                .find(|dev| find_device(&self.input_devices, dev).is_none())
+            {
+                msg(
+                    "Error",
+                    &format!("Player {}'s device \"{}\" is not connected!", i + 1, dev.name),
+                );
+                return;
+            }
+        }
+
+        prepare_instances(
+            &mut self.instances,
+            &self.monitors,
+            &self.profiles,
+            &self.options,
+        );
+
+        if let Err(err) =
+            remember_device_profiles(&mut self.device_profiles, &self.instances, &self.profiles)
+        {
+            println!("[partydeck] Couldn't save device profiles: {}", err);
+        }
+
+        let handler = self.launch_handler();
+
+        let last = SessionPreset::from_instances(
+            LAST_SESSION_PRESET,
+            handler.handler_dir_name(),
+            &self.instances,
+            &self.profiles,
+            &self.options,
+        );
+        if let Err(err) = store_preset(&mut self.presets, last) {
+            println!("[partydeck] Couldn't save the session setup: {}", err);
+        }
 
         let instances = self.instances.clone();
+        let monitors = self.monitors.clone();
         let dev_infos: Vec<DeviceInfo> = self.input_devices.iter().map(|p| p.info()).collect();
 
         let cfg = self.options.clone();
         let _ = save_cfg(&cfg);
 
-        self.cur_page = MenuPage::Home;
+        let session = new_shared_session(handler.display());
+        self.session = Some(session.clone());
+
+        self.cur_page = MenuPage::Session;
         self.spawn_task(
             "Launching...\n\nDon't press any buttons or move any analog sticks or mice.",
             move || {
                 sleep(std::time::Duration::from_secs_f32(1.5));
-
-                if let Err(err) = setup_profiles(&handler, &instances) {
-                    println!("[partydeck] Error mounting game directories: {}", err);
-                    msg("Failed mounting game directories", &format!("{err}"));
+                if start_session(&handler, &dev_infos, &instances, &monitors, &cfg, &session, true).is_err() {
+                    cleanup_session(false, true);
+                    session.lock().unwrap().finished = true;
                     return;
                 }
-                if handler.is_saved_handler()
-                    && !cfg.disable_mount_gamedirs
-                    && cfg.profile_unique_dirs
-                    && let Err(err) = fuse_overlayfs_mount_gamedirs(&handler, &instances)
-                {
-                    println!("[partydeck] Error mounting game directories: {}", err);
-                    msg("Failed mounting game directories", &format!("{err}"));
-                    return;
-                }
-                if let Err(err) = launch_game(&handler, &dev_infos, &instances, &cfg) {
-                    println!("[partydeck] Error launching instances: {}", err);
-                    msg("Launch Error", &format!("{err}"));
-                }
-                if cfg.enable_kwin_script {
-                    if let Err(err) = kwin_dbus_unload_script() {
-                        println!("[partydeck] Error unloading KWin script: {}", err);
-                        msg("Failed unloading KWin script", &format!("{err}"));
-                    }
-                }
-                if let Err(err) = remove_guest_profiles() {
-                    println!("[partydeck] Error removing guest profiles: {}", err);
-                    msg("Failed removing guest profiles", &format!("{err}"));
-                }
-                if let Err(err) = clear_tmp() {
-                    println!("[partydeck] Error removing tmp directory: {}", err);
-                    msg("Failed removing tmp directory", &format!("{err}"));
-                }
+                // Keep watching the instances in the background so the GUI stays usable
+                std::thread::spawn(move || {
+                    supervise(&session, &cfg, true);
+                    cleanup_session(cfg.offer_keep_guests, true);
+                    session.lock().unwrap().finished = true;
+                });
             },
         );
     }
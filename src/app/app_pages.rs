@@ -2,14 +2,21 @@ use super::app::{MenuPage, PartyApp, SettingsPage};
 use super::config::*;
 use crate::handler::*;
 use crate::input::*;
+use crate::keymap::*;
+use crate::layout::*;
+use crate::logs::*;
 use crate::paths::*;
+use crate::presets::*;
 use crate::profiles::*;
+use crate::remap::*;
+use crate::session::InstanceStatus;
+use crate::snapshots::*;
 use crate::util::*;
-use crate::monitor::get_monitors_sdl;
 
 use dialog::DialogBox;
 use eframe::egui::RichText;
 use eframe::egui::{self, Ui};
+use evdev::KeyCode;
 use rfd::FileDialog;
 use std::path::PathBuf;
 
@@ -19,6 +26,10 @@ macro_rules! cur_handler {
     };
 }
 
+fn chord_text(chord: &[PadButton]) -> String {
+    chord.iter().map(|btn| btn.name()).collect::<Vec<_>>().join(" + ")
+}
+
 impl PartyApp {
     pub fn display_page_main(&mut self, ui: &mut Ui) {
         ui.heading("Welcome to PartyDeck");
@@ -81,6 +92,7 @@ impl PartyApp {
                 if ui.button("Restore Defaults").clicked() {
                     self.options = PartyConfig::default();
                     self.input_devices = scan_input_devices(&self.options.pad_filter_type);
+                    self.reload_selected_layout();
                 }
             });
             ui.separator();
@@ -94,16 +106,107 @@ impl PartyApp {
             .max_height(ui.available_height() - 16.0)
             .auto_shrink(false)
             .show(ui, |ui| {
+                let mut selected_profile: Option<String> = None;
+                let mut rename: Option<String> = None;
+                let mut duplicate: Option<String> = None;
+                let mut avatar: Option<String> = None;
+                let mut delete: Option<String> = None;
                 for profile in &self.profiles {
-                    if ui.selectable_value(&mut 0, 1, profile).clicked() {
-                        if let Err(_) = std::process::Command::new("xdg-open")
-                            .arg(PATH_PARTY.join("profiles").join(profile))
-                            .status()
+                    ui.horizontal(|ui| {
+                        match profile_avatar(profile) {
+                            Some(path) => ui.add(
+                                egui::Image::new(format!("file://{}", path.display()))
+                                    .fit_to_exact_size(egui::vec2(16.0, 16.0))
+                                    .corner_radius(2),
+                            ),
+                            None => ui.label("👤"),
+                        };
+                        let selected =
+                            self.profile_remap.as_ref().is_some_and(|(p, _)| p == profile);
+                        if ui.selectable_label(selected, profile).clicked() {
+                            selected_profile = Some(profile.clone());
+                        }
+                        if ui.button("📁").on_hover_text("Open profile folder").clicked()
+                            && std::process::Command::new("xdg-open")
+                                .arg(PATH_PARTY.join("profiles").join(profile))
+                                .status()
+                                .is_err()
                         {
                             msg("Error", "Couldn't open profile directory!");
                         }
-                    };
+                        if ui.button("✏").on_hover_text("Rename").clicked() {
+                            rename = Some(profile.clone());
+                        }
+                        if ui.button("🗐").on_hover_text("Duplicate").clicked() {
+                            duplicate = Some(profile.clone());
+                        }
+                        if ui.button("🖼").on_hover_text("Set avatar").clicked() {
+                            avatar = Some(profile.clone());
+                        }
+                        if ui.button("🗑").on_hover_text("Delete").clicked() {
+                            delete = Some(profile.clone());
+                        }
+                    });
+                }
+                // The running instances use the profiles' directories
+                let changes_profile = rename.is_some()
+                    || duplicate.is_some()
+                    || avatar.is_some()
+                    || delete.is_some();
+                if changes_profile && self.session.is_some() {
+                    msg("Error", "Can't change profiles while a session is running!");
+                    (rename, duplicate, avatar, delete) = (None, None, None, None);
+                }
+                if let Some(profile) = selected_profile {
+                    let snapshots = scan_snapshots(&profile);
+                    self.profile_snapshots = Some((profile.clone(), snapshots));
+                    let remap = load_remap(&profile);
+                    self.profile_remap = Some((profile, remap));
                 }
+                if let Some(profile) = rename {
+                    self.rename_profile(&profile);
+                }
+                if let Some(profile) = duplicate
+                    && let Some(name) = dialog::Input::new("Enter name (must be alphanumeric):")
+                        .title("Duplicate Profile")
+                        .default(format!("{profile}2"))
+                        .show()
+                        .expect("Could not display dialog box")
+                {
+                    if !valid_profile_name(&name) {
+                        msg("Error", "Invalid name");
+                    } else if let Err(err) = duplicate_profile(&profile, &name) {
+                        msg("Error", &format!("Couldn't duplicate profile: {err}"));
+                    }
+                    self.profiles = scan_profiles(false);
+                }
+                if let Some(profile) = avatar
+                    && let Some(file) = FileDialog::new()
+                        .set_title("Choose Avatar")
+                        .add_filter("Image", &["png", "jpg", "jpeg"])
+                        .pick_file()
+                {
+                    match set_profile_avatar(&profile, &file) {
+                        Ok(()) => {
+                            if let Some(path) = profile_avatar(&profile) {
+                                ui.ctx().forget_image(&format!("file://{}", path.display()));
+                            }
+                        }
+                        Err(err) => msg("Error", &format!("Couldn't set avatar: {err}")),
+                    }
+                }
+                if let Some(profile) = delete
+                    && yesno(
+                        "Delete Profile",
+                        &format!(
+                            "Delete the profile {profile}? All of its game saves will be deleted, and this can't be undone."
+                        ),
+                    )
+                {
+                    self.delete_profile(&profile);
+                }
+                self.display_remap_editor(ui);
+                self.display_snapshots(ui);
             });
         if ui.button("New").clicked() {
             if let Some(name) = dialog::Input::new("Enter name (must be alphanumeric):")
@@ -111,7 +214,7 @@ impl PartyApp {
                 .show()
                 .expect("Could not display dialog box")
             {
-                if !name.is_empty() && name.chars().all(char::is_alphanumeric) {
+                if valid_profile_name(&name) {
                     create_profile(&name).unwrap();
                 } else {
                     msg("Error", "Invalid name");
@@ -121,6 +224,161 @@ impl PartyApp {
         }
     }
 
+    fn display_remap_editor(&mut self, ui: &mut Ui) {
+        let Some((profile, remap)) = &mut self.profile_remap else {
+            return;
+        };
+        let before = remap.clone();
+        // Deadzone sliders are only saved once let go of, rather than on every step of a drag
+        let mut dragging = false;
+        let mut drag_stopped = false;
+
+        ui.separator();
+        ui.label(RichText::new(format!("Button remapping for {profile}")).strong());
+        if !self.options.virtual_gamepads {
+            ui.label(
+                RichText::new("⚠ Only applies when virtual gamepads are enabled in Settings")
+                    .color(ui.visuals().warn_fg_color),
+            );
+        }
+
+        let mut unmapped: Option<String> = None;
+        for (from, to) in remap.buttons.iter_mut() {
+            ui.horizontal(|ui| {
+                ui.label(format!("{} ➡", button_label(from)));
+                egui::ComboBox::from_id_salt(format!("remap{from}"))
+                    .selected_text(button_label(to))
+                    .show_ui(ui, |ui| {
+                        for (btn, label) in REMAP_BUTTONS {
+                            ui.selectable_value(to, btn.to_string(), *label);
+                        }
+                    });
+                if ui.button("🗑").clicked() {
+                    unmapped = Some(from.clone());
+                }
+            });
+        }
+        if let Some(from) = unmapped {
+            remap.buttons.remove(&from);
+        }
+
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("remap_add")
+                .selected_text("➕ Remap a button")
+                .show_ui(ui, |ui| {
+                    for (btn, label) in REMAP_BUTTONS {
+                        if !remap.buttons.contains_key(*btn)
+                            && ui.selectable_label(false, *label).clicked()
+                        {
+                            remap.buttons.insert(btn.to_string(), btn.to_string());
+                        }
+                    }
+                });
+            if ui.button("Swap A/B").clicked() {
+                remap.buttons.insert("BTN_SOUTH".to_string(), "BTN_EAST".to_string());
+                remap.buttons.insert("BTN_EAST".to_string(), "BTN_SOUTH".to_string());
+            }
+        });
+
+        egui::Grid::new("remap_axes").show(ui, |ui| {
+            for (axis, label) in REMAP_AXES {
+                ui.label(*label);
+                let mut inverted = remap.inverted_axes.iter().any(|a| a == axis);
+                if ui.checkbox(&mut inverted, "Invert").changed() {
+                    match inverted {
+                        true => remap.inverted_axes.push(axis.to_string()),
+                        false => remap.inverted_axes.retain(|a| a != axis),
+                    }
+                }
+                let mut deadzone = remap.deadzones.get(*axis).copied().unwrap_or(0.0) * 100.0;
+                let slider = egui::Slider::new(&mut deadzone, 0.0..=50.0)
+                    .text("Deadzone")
+                    .suffix("%")
+                    .integer();
+                let response = ui.add(slider);
+                if response.changed() {
+                    match deadzone > 0.0 {
+                        true => remap.deadzones.insert(axis.to_string(), deadzone / 100.0),
+                        false => remap.deadzones.remove(*axis),
+                    };
+                }
+                dragging |= response.dragged();
+                drag_stopped |= response.drag_stopped();
+                ui.end_row();
+            }
+        });
+
+        if ((*remap != before && !dragging) || drag_stopped)
+            && let Err(err) = save_remap(profile, remap)
+        {
+            msg("Error", &format!("Couldn't save button remapping: {err}"));
+        }
+    }
+
+    fn display_snapshots(&mut self, ui: &mut Ui) {
+        let Some((profile, snapshots)) = &self.profile_snapshots else {
+            return;
+        };
+        let profile = profile.clone();
+
+        ui.separator();
+        ui.label(RichText::new(format!("Save snapshots for {profile}")).strong());
+        if snapshots.is_empty() {
+            ui.label(match self.options.save_snapshots {
+                true => "No snapshots yet. One is taken of a game's saves each time it's launched.",
+                false => "No snapshots. Turn on save snapshots in Settings to take them before each launch.",
+            });
+            return;
+        }
+
+        let mut restore: Option<usize> = None;
+        let mut delete: Option<usize> = None;
+        egui::Grid::new("snapshots").show(ui, |ui| {
+            for (i, snapshot) in snapshots.iter().enumerate() {
+                let game = self
+                    .handlers
+                    .iter()
+                    .find(|h| h.handler_dir_name() == snapshot.handler)
+                    .map_or(snapshot.handler.as_str(), |h| h.display());
+                ui.label(game);
+                ui.label(&snapshot.date);
+                if ui.button("⟲ Restore").clicked() {
+                    restore = Some(i);
+                }
+                if ui.button("🗑").on_hover_text("Delete").clicked() {
+                    delete = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+
+        let restore = restore.map(|i| snapshots[i].clone());
+        let delete = delete.map(|i| snapshots[i].clone());
+        if let Some(snapshot) = restore {
+            if self.session.is_some() {
+                msg("Error", "Can't restore saves while a session is running!");
+            } else if yesno(
+                "Restore Snapshot",
+                &format!(
+                    "Restore {profile}'s saves from {}? Progress made since then will be lost, including in other games that share the profile's Windows, home or Steam data.",
+                    snapshot.date
+                ),
+            ) {
+                self.spawn_task("Restoring saves...", move || {
+                    if let Err(err) = restore_snapshot(&profile, &snapshot) {
+                        msg("Error", &format!("Couldn't restore snapshot: {err}"));
+                    }
+                });
+            }
+        }
+        if let Some(snapshot) = delete {
+            if let Err(err) = delete_snapshot(&snapshot) {
+                msg("Error", &format!("Couldn't delete snapshot: {err}"));
+            }
+            self.refresh_profile_snapshots();
+        }
+    }
+
     pub fn display_page_edit_handler(&mut self, ui: &mut Ui) {
         let h = match &mut self.handler_edit {
             Some(handler) => handler,
@@ -287,6 +545,10 @@ impl PartyApp {
         ui.separator();
 
         let h = cur_handler!(self);
+        let mut open_instances = false;
+        let mut preset_to_load: Option<usize> = None;
+        let mut preset_to_delete: Option<usize> = None;
+        let mut play_again = false;
 
         ui.horizontal(|ui| {
             let playbtn = ui.add(egui::Button::image_and_text(
@@ -318,15 +580,41 @@ impl PartyApp {
                     self.handler_edit = Some(h.clone());
                     self.cur_page = MenuPage::EditHandler;
                 } else {
-                    self.instances.clear();
-                    self.input_devices = scan_input_devices(&self.options.pad_filter_type);
-                    self.monitors = get_monitors_sdl();
-                    self.profiles = scan_profiles(true);
-                    self.instance_add_dev = None;
-                    self.cur_page = MenuPage::Instances;
+                    open_instances = true;
                 }
             }
 
+            let presets: Vec<usize> = (0..self.presets.len())
+                .filter(|p| self.presets[*p].handler == h.handler_dir_name())
+                .collect();
+            if let Some(&last) = presets
+                .iter()
+                .find(|p| self.presets[**p].name == LAST_SESSION_PRESET)
+                && ui.button("🔁 Play again with last setup").clicked()
+            {
+                preset_to_load = Some(last);
+                play_again = true;
+            }
+            if presets.iter().any(|p| self.presets[*p].name != LAST_SESSION_PRESET) {
+                egui::ComboBox::from_id_salt("presets")
+                    .selected_text("Presets")
+                    .show_ui(ui, |ui| {
+                        for &p in presets
+                            .iter()
+                            .filter(|p| self.presets[**p].name != LAST_SESSION_PRESET)
+                        {
+                            ui.horizontal(|ui| {
+                                if ui.selectable_label(false, &self.presets[p].name).clicked() {
+                                    preset_to_load = Some(p);
+                                }
+                                if ui.button("🗑").clicked() {
+                                    preset_to_delete = Some(p);
+                                }
+                            });
+                        }
+                    });
+            }
+
             ui.add(egui::Separator::default().vertical());
             if h.win() {
                 ui.label(" Proton");
@@ -360,6 +648,18 @@ impl PartyApp {
                     }
                 });
             });
+
+        if let Some(p) = preset_to_delete
+            && let Err(err) = delete_preset(&mut self.presets, p)
+        {
+            msg("Error", &format!("Couldn't delete preset: {err}"));
+        } else if open_instances || preset_to_load.is_some() {
+            self.open_instances_page(preset_to_load);
+            // Launching checks that all of the players' devices are connected
+            if play_again {
+                self.prepare_game_launch();
+            }
+        }
     }
 
     pub fn display_page_instances(&mut self, ui: &mut Ui) {
@@ -396,14 +696,39 @@ impl PartyApp {
             ui.add(egui::Separator::default().vertical());
         });
 
+        if self.options.virtual_gamepads && self.options.keyboard_gamepads {
+            ui.horizontal(|ui| {
+                ui.label("🖮");
+                for keymap in &self.keymaps {
+                    if let Some(join) = keymap.key_for(KeyCode::BTN_SOUTH) {
+                        ui.label(format!("[{}]", key_name(join)));
+                        ui.label(format!("Join with {}", keymap.name));
+                        ui.add(egui::Separator::default().vertical());
+                    }
+                }
+            });
+        }
+
         ui.separator();
 
-        let mut devices_to_remove: Vec<(usize, usize)> = Vec::new();
+        let mut devices_to_remove: Vec<(usize, DeviceId)> = Vec::new();
+        let mut hovered_device: Option<DeviceId> = None;
         for (i, instance) in &mut self.instances.iter_mut().enumerate() {
             ui.horizontal(|ui| {
                 ui.label(format!("{}", i + 1));
 
-                ui.label("👤");
+                let avatar = match instance.profselection {
+                    0 => None,
+                    sel => self.profiles.get(sel).and_then(|p| profile_avatar(p)),
+                };
+                match avatar {
+                    Some(path) => ui.add(
+                        egui::Image::new(format!("file://{}", path.display()))
+                            .fit_to_exact_size(egui::vec2(16.0, 16.0))
+                            .corner_radius(2),
+                    ),
+                    None => ui.label("👤"),
+                };
                 egui::ComboBox::from_id_salt(format!("{i}")).show_index(
                     ui,
                     &mut instance.profselection,
@@ -435,30 +760,43 @@ impl PartyApp {
                     }
                 }
             });
-            for &dev in instance.devices.iter() {
-                let mut dev_text = RichText::new(format!(
-                    "{} {}",
-                    self.input_devices[dev].emoji(),
-                    self.input_devices[dev].fancyname()
-                ));
-
-                if self.input_devices[dev].has_button_held() {
-                    dev_text = dev_text.strong();
-                }
+            for dev in instance.devices.iter() {
+                let dev_text = match find_device(&self.input_devices, dev) {
+                    Some(d) => {
+                        let pad = &self.input_devices[d];
+                        let mut text = format!("{} {}", pad.emoji(), pad.fancyname());
+                        if pad.device_type() == DeviceType::Keyboard
+                            && instance.has_keyboard_gamepad(&self.options)
+                            && let Some(keymap) = instance.keymap.and_then(|k| self.keymaps.get(k))
+                        {
+                            text.push_str(&format!(" (🎮 {})", keymap.name));
+                        }
+                        let text = RichText::new(text);
+                        match pad.has_button_held() {
+                            true => text.strong(),
+                            false => text,
+                        }
+                    }
+                    None => RichText::new(format!("⚠ {} (disconnected)", dev.name))
+                        .color(ui.visuals().warn_fg_color),
+                };
 
                 ui.horizontal(|ui| {
                     ui.label("    ");
-                    ui.label(dev_text);
+                    if ui.label(dev_text).hovered() {
+                        hovered_device = Some(dev.clone());
+                    }
                     if ui.button("🗑").clicked() {
-                        devices_to_remove.push((i, dev));
+                        devices_to_remove.push((i, dev.clone()));
                     }
                 });
             }
         }
 
         for (i, d) in devices_to_remove {
-            self.remove_device_instance(i, d);
+            self.remove_device_instance(i, &d);
         }
+        self.rumble_on_hover(hovered_device);
 
         if self.instances.len() > 0 {
             ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
@@ -470,20 +808,303 @@ impl PartyApp {
                     if ui.button("Start").clicked() {
                         self.prepare_game_launch();
                     }
+                    if ui.button("🗐 Show Launch Plan").clicked() {
+                        self.show_launch_plan();
+                    }
+                    if ui.button("💾 Save Preset").clicked()
+                        && let Some(name) = dialog::Input::new("Enter a name for this setup:")
+                            .title("Save Preset")
+                            .show()
+                            .expect("Could not display dialog box")
+                    {
+                        let name = name.trim();
+                        if !name.is_empty() && name != LAST_SESSION_PRESET {
+                            self.save_preset(name);
+                        } else {
+                            msg("Error", "Invalid name");
+                        }
+                    }
                 });
                 ui.separator();
+                self.display_layout_preview(ui);
+                ui.horizontal(|ui| {
+                    ui.label("Layout");
+                    let selected = self
+                        .layouts
+                        .iter()
+                        .find(|l| l.id == self.options.layout)
+                        .map_or("Grid (default)", |l| l.name.as_str());
+                    let mut changed = false;
+                    egui::ComboBox::from_id_salt("layout")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            changed |= ui.selectable_value(&mut self.options.layout, String::new(), "Grid (default)").changed();
+                            for layout in &self.layouts {
+                                changed |= ui.selectable_value(&mut self.options.layout, layout.id.clone(), &layout.name).changed();
+                            }
+                        });
+                    if ui.button("🔄").on_hover_text("Reload layouts").clicked() {
+                        self.layouts = scan_layouts();
+                        changed = true;
+                    }
+                    if changed {
+                        self.reload_selected_layout();
+                    }
+                });
             });
         }
     }
 
-    pub fn display_settings_general(&mut self, ui: &mut Ui) {
-        let enable_kwin_script_check = ui.checkbox(
-            &mut self.options.enable_kwin_script,
-            "(KDE) Automatically resize/reposition instances using KWin script",
-        );
-        if enable_kwin_script_check.hovered() {
-            self.infotext = "DEFAULT: Enabled\n\n Resizes/repositions instances to fit the screen using a KWin script. If using a desktop environment or window manager other than KDE Plasma, uncheck this; note that you will need to manually resize and reposition the windows.".to_string();
+    // Draws each monitor in use with the area every player will get on it
+    pub fn display_layout_preview(&self, ui: &mut Ui) {
+        const COLORS: [egui::Color32; 4] = [
+            egui::Color32::from_rgb(70, 110, 180),
+            egui::Color32::from_rgb(180, 80, 80),
+            egui::Color32::from_rgb(80, 150, 90),
+            egui::Color32::from_rgb(170, 140, 60),
+        ];
+
+        let monitors: Vec<usize> = match self.options.gamescope_sdl_backend {
+            true => (0..self.monitors.len())
+                .filter(|m| self.instances.iter().any(|i| i.monitor == *m))
+                .collect(),
+            false => vec![0],
+        };
+
+        ui.horizontal(|ui| {
+            for mon in monitors {
+                let players: Vec<usize> = (0..self.instances.len())
+                    .filter(|&i| !self.options.gamescope_sdl_backend || self.instances[i].monitor == mon)
+                    .collect();
+                let layout = layout_for(players.len(), self.selected_layout.as_ref(), &self.options);
+
+                let monitor = &self.monitors[mon];
+                let height = 80.0;
+                let width = height * monitor.width() as f32 / monitor.height().max(1) as f32;
+                let (response, painter) =
+                    ui.allocate_painter(egui::vec2(width, height), egui::Sense::hover());
+                let frame = response.rect;
+                painter.rect_filled(frame, 2.0, ui.visuals().extreme_bg_color);
+
+                for (rect, &i) in layout.iter().zip(&players) {
+                    let area = egui::Rect::from_min_size(
+                        frame.min + egui::vec2(rect.x * frame.width(), rect.y * frame.height()),
+                        egui::vec2(rect.w * frame.width(), rect.h * frame.height()),
+                    )
+                    .shrink(1.0);
+                    painter.rect_filled(area, 2.0, COLORS[i % COLORS.len()]);
+                    let profile = self
+                        .profiles
+                        .get(self.instances[i].profselection)
+                        .map_or("", |p| p.as_str());
+                    painter.text(
+                        area.center(),
+                        egui::Align2::CENTER_CENTER,
+                        format!("{} {}", i + 1, profile),
+                        egui::FontId::proportional(9.0),
+                        egui::Color32::WHITE,
+                    );
+                }
+                response.on_hover_text(monitor.name());
+            }
+        });
+    }
+
+    pub fn display_page_session(&mut self, ui: &mut Ui) {
+        ui.heading("Session");
+        ui.separator();
+
+        let Some(session) = self.session.clone() else {
+            ui.label("No session is running.");
+            return;
+        };
+        let mut session = session.lock().unwrap();
+
+        if session.instances.is_empty() {
+            ui.label("Launching...");
+            return;
         }
+        ui.label(format!("🎮 {}", session.handler));
+
+        let ending = session.is_ending();
+        let mut to_restart: Option<usize> = None;
+        egui::Grid::new("session_instances")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                for (i, instance) in session.instances.iter().enumerate() {
+                    ui.label(format!("{}", i + 1));
+                    ui.label(format!("👤 {}", instance.plan.profile));
+                    ui.label(match instance.pid {
+                        Some(pid) => format!("PID {pid}"),
+                        None => String::new(),
+                    });
+
+                    let status_text = RichText::new(instance.status.label());
+                    ui.label(match instance.status {
+                        InstanceStatus::Running => status_text,
+                        InstanceStatus::Exited => status_text.weak(),
+                        _ => status_text.color(ui.visuals().error_fg_color),
+                    });
+
+                    if !instance.is_running() && !ending {
+                        if ui.button("⟳ Restart").clicked() {
+                            to_restart = Some(i);
+                        }
+                    } else if instance.restarts > 0 {
+                        ui.label(format!("Restarted {}×", instance.restarts));
+                    } else {
+                        ui.label("");
+                    }
+                    ui.end_row();
+                }
+            });
+
+        // Reported after the session is unlocked, since dialogs block
+        let mut restart_err: Option<String> = None;
+        if let Some(i) = to_restart
+            && let Err(err) = session.restart(i)
+        {
+            // Placement can fail after the instance itself has started again
+            restart_err = Some(match session.instances[i].is_running() {
+                true => err.to_string(),
+                false => {
+                    println!("[partydeck] Failed to restart instance {}: {}", i + 1, err);
+                    format!("Failed to restart instance {}: {}", i + 1, err)
+                }
+            });
+        }
+
+        ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+            if ending {
+                ui.label("Ending session...");
+            } else {
+                ui.horizontal(|ui| {
+                    if ui.button("⏹ End Session").clicked() {
+                        session.request_end();
+                    }
+                    if !self.options.end_session_chord.is_empty() {
+                        ui.label(format!(
+                            "or hold {} for {} seconds",
+                            chord_text(&self.options.end_session_chord),
+                            self.options.end_session_chord_secs
+                        ));
+                    }
+                });
+            }
+            ui.separator();
+        });
+
+        drop(session);
+        if let Some(err) = restart_err {
+            msg("Error", &err);
+        }
+    }
+
+    pub fn display_page_logs(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.heading("Logs");
+            if ui.button("🔄").clicked() {
+                self.log_files = scan_log_files();
+                self.log_selected = 0;
+                self.log_read_at = None;
+            }
+            if ui.button("Open Folder").clicked()
+                && std::process::Command::new("xdg-open")
+                    .arg(logs_dir())
+                    .status()
+                    .is_err()
+            {
+                msg("Error", "Couldn't open logs folder!");
+            }
+        });
+        ui.separator();
+
+        if self.log_files.is_empty() {
+            ui.label("No logs yet.");
+            return;
+        }
+
+        // Re-read the selected log every second so it can be followed while a session runs
+        if self
+            .log_read_at
+            .is_none_or(|t| t.elapsed() > std::time::Duration::from_secs(1))
+        {
+            let path = &self.log_files[self.log_selected.min(self.log_files.len() - 1)];
+            self.log_text = read_log_tail(path, 500);
+            self.log_read_at = Some(std::time::Instant::now());
+        }
+
+        ui.horizontal_top(|ui| {
+            egui::ScrollArea::vertical()
+                .id_salt("log_files")
+                .max_width(180.0)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    for (i, path) in self.log_files.iter().enumerate() {
+                        let name = match path.strip_prefix(logs_dir()) {
+                            Ok(rel) if rel == std::path::Path::new("partydeck.log") => {
+                                "PartyDeck".to_string()
+                            }
+                            Ok(rel) => rel.display().to_string(),
+                            Err(_) => path.display().to_string(),
+                        };
+                        if ui
+                            .selectable_value(&mut self.log_selected, i, RichText::new(name).small())
+                            .clicked()
+                        {
+                            self.log_read_at = None;
+                        }
+                    }
+                });
+            ui.separator();
+            egui::ScrollArea::both()
+                .id_salt("log_text")
+                .stick_to_bottom(true)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    ui.label(RichText::new(&self.log_text).monospace().small());
+                });
+        });
+    }
+
+    pub fn display_settings_general(&mut self, ui: &mut Ui) {
+        const CHORD_BUTTONS: [PadButton; 6] = [
+            PadButton::ABtn,
+            PadButton::BBtn,
+            PadButton::XBtn,
+            PadButton::YBtn,
+            PadButton::SelectBtn,
+            PadButton::StartBtn,
+        ];
+
+        ui.horizontal(|ui| {
+            let placement_label = ui.label("Window placement");
+            let mut hovered = placement_label.hovered();
+            let r_off = ui.radio(!self.options.window_placement, "Off");
+            if r_off.clicked() {
+                self.options.window_placement = false;
+            }
+            hovered |= r_off.hovered();
+            for (backend, text) in [
+                (PlacementBackend::KWin, "KWin script"),
+                (PlacementBackend::X11, "X11"),
+                (PlacementBackend::Sway, "Sway"),
+                (PlacementBackend::Hyprland, "Hyprland"),
+            ] {
+                let selected =
+                    self.options.window_placement && self.options.placement_backend == backend;
+                let r = ui.radio(selected, text);
+                if r.clicked() {
+                    self.options.window_placement = true;
+                    self.options.placement_backend = backend;
+                }
+                hovered |= r.hovered();
+            }
+            if hovered {
+                self.infotext = "DEFAULT: KWin script\n\nHow instance windows are resized and repositioned to fit the screen according to the layout. \"KWin script\" is for KDE Plasma and PartyDeck's own KWin session (including SteamOS Gaming Mode). \"X11\" works with most other X11 window managers, such as GNOME on Xorg, XFCE and i3. \"Sway\" and \"Hyprland\" float and position the windows through the compositor's IPC socket, and tile them again when the session ends. If none of these fit your desktop, choose \"Off\"; note that you will need to manually resize and reposition the windows.".to_string();
+            }
+        });
 
         ui.horizontal(|ui| {
             let split_style_label = ui.label("Split style");
@@ -538,6 +1159,50 @@ impl PartyApp {
             self.infotext = "DEFAULT: Enabled\n\nGives each profile their own data directories. For Windows games, this is the C:\\Users\\steamuser folder, for Linux native games this is the HOME directory. Note that disabling this means that PartyDeck instances may potentially modify your game's actual save data on disk.".to_string();
         }
 
+        let offer_keep_guests_check = ui.checkbox(
+            &mut self.options.offer_keep_guests,
+            "Offer to keep guest progress",
+        );
+        if offer_keep_guests_check.hovered() {
+            self.infotext = "DEFAULT: Enabled\n\nWhen a session with guest players ends, ask whether to keep each guest's progress. A kept guest becomes a new profile with their game saves; otherwise guests are deleted along with their saves.".to_string();
+        }
+
+        ui.horizontal(|ui| {
+            let save_snapshots_check =
+                ui.checkbox(&mut self.options.save_snapshots, "Snapshot saves before launching");
+            let kept = ui.add_enabled(
+                self.options.save_snapshots,
+                egui::DragValue::new(&mut self.options.save_snapshots_kept)
+                    .range(1..=50)
+                    .prefix("Keep "),
+            );
+            if save_snapshots_check.hovered() || kept.hovered() {
+                self.infotext = "DEFAULT: Disabled, keep 5\n\nBefore each launch, zips up each player profile's saves for the game, along with the profile's Windows, home and Steam data. This can take a while for profiles with a lot of data. Only the newest snapshots of each game are kept. Snapshots can be restored from the Profiles page.".to_string();
+            }
+        });
+
+        let virtual_gamepads_check = ui.checkbox(
+            &mut self.options.virtual_gamepads,
+            "Give each player a virtual gamepad",
+        );
+        if virtual_gamepads_check.hovered() {
+            self.infotext = "DEFAULT: Disabled\n\nInstead of hiding other players' controllers from each instance, PartyDeck takes exclusive hold of every controller and gives each player one virtual gamepad carrying their input. Each instance then only has its own gamepad in /dev/input. Try this if a game still sees every controller, or gets confused by the hidden ones. Rumble isn't passed through to the controllers in this mode.".to_string();
+        }
+
+        let keyboard_gamepads_check = ui.add_enabled(
+            self.options.virtual_gamepads,
+            egui::Checkbox::new(
+                &mut self.options.keyboard_gamepads,
+                "Let keyboards be used as gamepads",
+            ),
+        );
+        if keyboard_gamepads_check.hovered() {
+            self.infotext = format!(
+                "DEFAULT: Disabled\n\nRequires virtual gamepads. Lets players without a controller turn a keyboard, or half of one, into their virtual gamepad; two players can share one keyboard this way. On the Instances page, press a keymap's A key to join with it and its B key to leave. By default, the left half uses WASD with Space as A, and the right half uses the arrow keys with / as A. The keymaps can be edited in {}.",
+                keymaps_path().display()
+            );
+        }
+
         let allow_multiple_instances_on_same_device_check = ui.checkbox(
             &mut self.options.allow_multiple_instances_on_same_device,
             "(Debug) Allow multiple instances from one gamepad",
@@ -554,6 +1219,32 @@ impl PartyApp {
             self.infotext = "DEFAULT: Disabled\n\nBy default, PartyDeck mounts game directories using fuse-overlayfs to let each instance write to the game's directory without conflicting with each other or affecting the game's installation. In addition, this lets handlers overlay content like mods or config files onto the game directory. Enabling this forces instances to launch from the original game directory without mounting, which will prevent handlers from using built-in mods, but may be useful for diagnosing issues.".to_string();
         }
 
+        ui.horizontal(|ui| {
+            let chord_label = ui.label("End session combination");
+            let mut hovered = chord_label.hovered();
+            for btn in CHORD_BUTTONS {
+                let selected = self.options.end_session_chord.contains(&btn);
+                let r = ui.selectable_label(selected, btn.name());
+                if r.clicked() {
+                    match selected {
+                        true => self.options.end_session_chord.retain(|b| *b != btn),
+                        false => self.options.end_session_chord.push(btn),
+                    }
+                }
+                hovered |= r.hovered();
+            }
+            ui.label("Hold for");
+            let secs = ui.add(
+                egui::DragValue::new(&mut self.options.end_session_chord_secs)
+                    .range(0.5..=10.0)
+                    .speed(0.1)
+                    .suffix("s"),
+            );
+            if hovered || secs.hovered() {
+                self.infotext = "DEFAULT: Select + Start, 3s\n\nHolding these buttons down together on any controller during a game session ends the session, closing all game instances. Deselect all buttons to disable this.".to_string();
+            }
+        });
+
         ui.separator();
 
         if ui.button("Open PartyDeck Data Folder").clicked() {
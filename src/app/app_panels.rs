@@ -4,7 +4,6 @@ use crate::handler::import_pd2;
 use crate::handler::scan_handlers;
 use crate::input::*;
 use crate::monitor::get_monitors_sdl;
-use crate::profiles::scan_profiles;
 use crate::util::*;
 
 use eframe::egui::Popup;
@@ -54,12 +53,23 @@ impl PartyApp {
                     .selected(self.cur_page == MenuPage::Profiles),
             );
             if profilesbtn.clicked() {
-                self.profiles = scan_profiles(false);
-                self.cur_page = MenuPage::Profiles;
+                self.go_to_page(MenuPage::Profiles);
+            }
+
+            if self.session.is_some() {
+                let sessionbtn =
+                    ui.add(egui::Button::new("▶ Session").selected(self.cur_page == MenuPage::Session));
+                if sessionbtn.clicked() {
+                    self.cur_page = MenuPage::Session;
+                }
+            }
+
+            let logsbtn = ui.add(egui::Button::new("📜").selected(self.cur_page == MenuPage::Logs));
+            if logsbtn.clicked() {
+                self.go_to_page(MenuPage::Logs);
             }
 
             if ui.button("🎮 🔄").clicked() {
-                self.instances.clear();
                 self.input_devices = scan_input_devices(&self.options.pad_filter_type);
             }
             
@@ -168,7 +178,7 @@ impl PartyApp {
                 dev_text = dev_text.strong();
             }
 
-            ui.label(dev_text);
+            ui.label(dev_text).on_hover_text(pad.id().to_string());
         }
 
         ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
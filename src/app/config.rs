@@ -1,3 +1,4 @@
+use crate::input::PadButton;
 use crate::paths::*;
 
 use std::error::Error;
@@ -14,14 +15,37 @@ pub enum PadFilterType {
     OnlySteamInput,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
+pub enum PlacementBackend {
+    #[default]
+    KWin,
+    X11,
+    Sway,
+    Hyprland,
+}
+
 fn default_true() -> bool {
     true
 }
 
+fn default_end_session_chord() -> Vec<PadButton> {
+    vec![PadButton::SelectBtn, PadButton::StartBtn]
+}
+
+fn default_save_snapshots_kept() -> usize {
+    5
+}
+
+fn default_end_session_chord_secs() -> f64 {
+    3.0
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct PartyConfig {
-    #[serde(default = "default_true")]
-    pub enable_kwin_script: bool,
+    #[serde(default = "default_true", alias = "enable_kwin_script")]
+    pub window_placement: bool,
+    #[serde(default)]
+    pub placement_backend: PlacementBackend,
     #[serde(default = "default_true")]
     pub gamescope_fix_lowres: bool,
     #[serde(default = "default_true")]
@@ -39,19 +63,36 @@ pub struct PartyConfig {
     #[serde(default)]
     pub vertical_two_player: bool,
     #[serde(default)]
+    pub layout: String,
+    #[serde(default)]
     pub pad_filter_type: PadFilterType,
     #[serde(default)]
+    pub virtual_gamepads: bool,
+    #[serde(default)]
+    pub keyboard_gamepads: bool,
+    #[serde(default = "default_true")]
+    pub offer_keep_guests: bool,
+    #[serde(default)]
+    pub save_snapshots: bool,
+    #[serde(default = "default_save_snapshots_kept")]
+    pub save_snapshots_kept: usize,
+    #[serde(default)]
     pub allow_multiple_instances_on_same_device: bool,
     #[serde(default = "default_true")]
     pub profile_unique_dirs: bool,
     #[serde(default)]
     pub disable_mount_gamedirs: bool,
+    #[serde(default = "default_end_session_chord")]
+    pub end_session_chord: Vec<PadButton>,
+    #[serde(default = "default_end_session_chord_secs")]
+    pub end_session_chord_secs: f64,
 }
 
 impl Default for PartyConfig {
     fn default() -> Self {
         PartyConfig {
-            enable_kwin_script: true,
+            window_placement: true,
+            placement_backend: PlacementBackend::KWin,
             gamescope_fix_lowres: true,
             gamescope_sdl_backend: true,
             gamescope_force_grab_cursor: false,
@@ -60,10 +101,18 @@ impl Default for PartyConfig {
             proton_separate_pfxs: true,
             proton_wow64: true,
             vertical_two_player: false,
+            layout: String::new(),
             pad_filter_type: PadFilterType::NoSteamInput,
+            virtual_gamepads: false,
+            keyboard_gamepads: false,
+            offer_keep_guests: true,
+            save_snapshots: false,
+            save_snapshots_kept: default_save_snapshots_kept(),
             allow_multiple_instances_on_same_device: false,
             profile_unique_dirs: true,
             disable_mount_gamedirs: false,
+            end_session_chord: default_end_session_chord(),
+            end_session_chord_secs: default_end_session_chord_secs(),
         }
     }
 }
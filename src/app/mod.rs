@@ -5,4 +5,6 @@ mod config;
 
 pub use app::PartyApp;
 pub use config::PadFilterType;
+pub use config::load_cfg;
 pub use config::PartyConfig;
+pub use config::PlacementBackend;
@@ -0,0 +1,217 @@
+use crate::app::load_cfg;
+use crate::handler::Handler;
+use crate::input::*;
+use crate::instance::*;
+use crate::layout::Rect;
+use crate::launch::{launch_plan, run_session};
+use crate::monitor::Monitor;
+use crate::paths::PATH_PARTY;
+use crate::profiles::scan_profiles;
+
+use std::error::Error;
+use std::path::{Component, Path, PathBuf};
+
+pub struct PlayerArg {
+    pub profile: String,
+    pub devices: Vec<String>,
+    pub monitor: usize,
+}
+
+pub struct LaunchArgs {
+    pub handler: String,
+    pub players: Vec<PlayerArg>,
+    pub dry_run: bool,
+    pub output: Option<PathBuf>,
+}
+
+// Parses the arguments following "partydeck launch"
+pub fn parse_launch_args(args: &[String]) -> Result<LaunchArgs, Box<dyn Error>> {
+    let mut handler = String::new();
+    let mut players: Vec<PlayerArg> = Vec::new();
+    let mut dry_run = false;
+    let mut output: Option<PathBuf> = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--handler" => {
+                handler = iter.next().ok_or("--handler requires a value")?.clone();
+            }
+            "--player" => {
+                let spec = iter.next().ok_or("--player requires a value")?;
+                players.push(parse_player_arg(spec)?);
+            }
+            "--dry-run" => dry_run = true,
+            "--output" => {
+                output = Some(PathBuf::from(iter.next().ok_or("--output requires a value")?));
+            }
+            _ => return Err(format!("Unknown launch option: {arg}").into()),
+        }
+    }
+
+    if handler.is_empty() {
+        return Err("No handler specified".into());
+    }
+    // The handler is a folder name, which mustn't lead out of the handlers directory
+    let leaves_dir = Path::new(&handler)
+        .components()
+        .any(|c| matches!(c, Component::CurDir | Component::ParentDir));
+    if leaves_dir || handler.contains('/') {
+        return Err(format!("Invalid handler name: {handler}").into());
+    }
+    if players.is_empty() {
+        return Err("At least one --player must be specified".into());
+    }
+
+    Ok(LaunchArgs {
+        handler,
+        players,
+        dry_run,
+        output,
+    })
+}
+
+// Parses a player spec such as "profile=Alice,device=/dev/input/by-id/...,monitor=0".
+// The device key can be given multiple times, e.g. for a keyboard and a mouse.
+fn parse_player_arg(spec: &str) -> Result<PlayerArg, Box<dyn Error>> {
+    let mut player = PlayerArg {
+        profile: String::new(),
+        devices: Vec::new(),
+        monitor: 0,
+    };
+
+    for pair in spec.split(',').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Invalid player option: {pair}"))?;
+        match key {
+            "profile" => player.profile = value.to_string(),
+            "device" => player.devices.push(value.to_string()),
+            "monitor" => {
+                player.monitor = value
+                    .parse()
+                    .map_err(|_| format!("Invalid monitor index: {value}"))?
+            }
+            _ => return Err(format!("Unknown player option: {key}").into()),
+        }
+    }
+
+    if player.devices.is_empty() {
+        return Err(format!("Player \"{spec}\" has no devices").into());
+    }
+
+    Ok(player)
+}
+
+// Launches a saved handler without opening the GUI
+pub fn launch_from_cli(args: &[String], monitors: &Vec<Monitor>) -> Result<(), Box<dyn Error>> {
+    let launch_args = parse_launch_args(args)?;
+    let cfg = load_cfg();
+
+    let handler_dir = PATH_PARTY.join("handlers").join(&launch_args.handler);
+    if !handler_dir.join("handler.json").exists() {
+        return Err(format!("Handler \"{}\" not found", launch_args.handler).into());
+    }
+    let handler = Handler::from_json(&handler_dir.join("handler.json"))?;
+
+    let input_devices = scan_input_devices(&cfg.pad_filter_type);
+    let profiles = scan_profiles(true);
+
+    let mut instances: Vec<Instance> = Vec::new();
+    for player in &launch_args.players {
+        let profselection = match player.profile.as_str() {
+            "" | "Guest" => 0,
+            name => profiles
+                .iter()
+                .position(|profile| profile == name)
+                .ok_or_else(|| format!("Profile \"{name}\" not found"))?,
+        };
+
+        if player.monitor >= monitors.len() {
+            return Err(format!("Monitor {} not found", player.monitor).into());
+        }
+
+        let mut devices: Vec<DeviceId> = Vec::new();
+        for device in &player.devices {
+            let d = find_input_device(&input_devices, device)?;
+            if !input_devices[d].enabled() {
+                return Err(format!("Device {device} is excluded by the controller filter").into());
+            }
+            let id = input_devices[d].id();
+            let in_use = devices.contains(id) || instances.iter().any(|i| i.devices.contains(id));
+            if in_use
+                && (!cfg.allow_multiple_instances_on_same_device
+                    || !input_devices[d].device_type().is_controller())
+            {
+                return Err(format!("Device {device} is assigned to more than one player").into());
+            }
+            devices.push(id.clone());
+        }
+
+        instances.push(Instance {
+            devices,
+            keymap: None,
+            profname: String::new(),
+            profselection,
+            monitor: player.monitor,
+            width: 0,
+            height: 0,
+            rect: Rect::FULL,
+        });
+    }
+
+    prepare_instances(&mut instances, monitors, &profiles, &cfg);
+
+    let dev_infos: Vec<DeviceInfo> = input_devices.iter().map(|p| p.info()).collect();
+
+    if launch_args.dry_run {
+        let plan = launch_plan(&handler, &dev_infos, &instances, &cfg, true)?;
+        let json = plan.to_json()?;
+        match launch_args.output {
+            Some(path) => std::fs::write(path, json)?,
+            None => println!("{json}"),
+        }
+        return Ok(());
+    }
+
+    run_session(&handler, &dev_infos, &instances, monitors, &cfg)
+}
+
+// Finds a device by its ID (as shown in the GUI's device list) or its event path;
+// symlinks such as /dev/input/by-id/... are resolved first
+fn find_input_device(input_devices: &[InputDevice], path: &str) -> Result<usize, Box<dyn Error>> {
+    if let Some(d) = input_devices.iter().position(|dev| dev.id().to_string() == path) {
+        return Ok(d);
+    }
+
+    let resolved = Path::new(path)
+        .canonicalize()
+        .map_err(|e| format!("Device {path} not found: {e}"))?;
+
+    input_devices
+        .iter()
+        .position(|dev| Path::new(dev.path()) == resolved)
+        .ok_or_else(|| format!("Device {path} is not a supported input device").into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(handler: &str) -> Vec<String> {
+        ["--handler", handler, "--player", "device=/dev/input/event0"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn handler_names_stay_in_the_handlers_directory() {
+        assert!(parse_launch_args(&args("game")).is_ok());
+        assert!(parse_launch_args(&args("my.game")).is_ok());
+        assert!(parse_launch_args(&args("my..game")).is_ok());
+        for handler in ["..", ".", "../game", "a/b", "/game", "game/..", "game/."] {
+            assert!(parse_launch_args(&args(handler)).is_err(), "{handler}");
+        }
+    }
+}
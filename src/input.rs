@@ -1,15 +1,58 @@
 use crate::app::PadFilterType;
+use crate::virtual_pads::VIRTUAL_PAD_NAME;
 
 use evdev::*;
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// How long a direction has to be held before it repeats, and how often it repeats after that
+const REPEAT_DELAY: Duration = Duration::from_millis(400);
+const REPEAT_INTERVAL: Duration = Duration::from_millis(100);
+// Share of the left stick's range, each side of center, that is ignored when navigating menus.
+// A device reporting a larger flat zone keeps its own, so worn sticks don't drift the UI
+const STICK_DEADZONE: f32 = 0.25;
 
 #[derive(Clone, PartialEq, Copy)]
 pub enum DeviceType {
     Gamepad,
+    // Flight sticks and arcade sticks that report generic joystick buttons
+    Joystick,
+    Wheel,
     Keyboard,
     Mouse,
+    // Touchpads and motion sensors are grouped with the controller they belong to;
+    // a touchpad on its own is treated as a mouse
+    Touchpad,
+    Motion,
     Other,
 }
 
+impl DeviceType {
+    // Devices that are handed to a single player and hidden from the others
+    pub fn is_controller(self) -> bool {
+        matches!(
+            self,
+            DeviceType::Gamepad | DeviceType::Joystick | DeviceType::Wheel
+        )
+    }
+
+    // Which node of a physical device represents it in the device list
+    fn priority(self) -> u8 {
+        match self {
+            DeviceType::Gamepad | DeviceType::Joystick | DeviceType::Wheel => 4,
+            DeviceType::Keyboard => 3,
+            DeviceType::Mouse => 2,
+            DeviceType::Touchpad => 1,
+            DeviceType::Motion | DeviceType::Other => 0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum PadButton {
     Left,
     Right,
@@ -21,6 +64,8 @@ pub enum PadButton {
     YBtn,
     StartBtn,
     SelectBtn,
+    LBBtn,
+    RBBtn,
 
     AKey,
     RKey,
@@ -30,19 +75,112 @@ pub enum PadButton {
     RightClick,
 }
 
+impl PadButton {
+    pub fn name(&self) -> &str {
+        match self {
+            PadButton::Left => "Left",
+            PadButton::Right => "Right",
+            PadButton::Up => "Up",
+            PadButton::Down => "Down",
+            PadButton::ABtn => "A",
+            PadButton::BBtn => "B",
+            PadButton::XBtn => "X",
+            PadButton::YBtn => "Y",
+            PadButton::StartBtn => "Start",
+            PadButton::SelectBtn => "Select",
+            PadButton::LBBtn => "LB",
+            PadButton::RBBtn => "RB",
+            PadButton::AKey => "A Key",
+            PadButton::RKey => "R Key",
+            PadButton::XKey => "X Key",
+            PadButton::ZKey => "Z Key",
+            PadButton::RightClick => "Right Click",
+        }
+    }
+}
+
+// Identifies a device across rescans and replugs, unlike its /dev/input/eventN path
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceId {
+    pub vendor: u16,
+    pub product: u16,
+    pub version: u16,
+    pub name: String,
+    pub uniq: String,
+    pub phys: String,
+    // Name of the device's /dev/input/by-path or /dev/input/by-id symlink, if it has one
+    pub link: String,
+    // Tells apart devices that are otherwise identical, such as Steam Input's virtual gamepads
+    pub index: usize,
+}
+
+impl PartialEq for DeviceId {
+    // A device with a serial number (or Bluetooth address) is the same device on any port;
+    // anything else is told apart by where it's plugged in. The udev symlink isn't compared
+    // since it may not exist yet when a hotplugged device first shows up.
+    fn eq(&self, other: &Self) -> bool {
+        if self.vendor != other.vendor
+            || self.product != other.product
+            || self.name != other.name
+            || self.index != other.index
+        {
+            return false;
+        }
+        if !self.uniq.is_empty() || !other.uniq.is_empty() {
+            return self.uniq == other.uniq;
+        }
+        self.version == other.version && self.phys == other.phys
+    }
+}
+
+impl fmt::Display for DeviceId {
+    // The form accepted by "partydeck launch --player device=..."
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:04x}:{:04x}", self.vendor, self.product)?;
+        if !self.uniq.is_empty() {
+            write!(f, ":{}", self.uniq)?;
+        } else if !self.phys.is_empty() {
+            write!(f, ":{}", self.phys)?;
+        }
+        if self.index > 0 {
+            write!(f, "#{}", self.index)?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct DeviceInfo {
+    pub id: DeviceId,
     pub path: String,
+    // Every other device node of the same physical device: event devices such as a
+    // controller's touchpad, joystick (jsN) devices and hidraw devices
+    pub nodes: Vec<String>,
     pub enabled: bool,
     pub device_type: DeviceType,
 }
 
 pub struct InputDevice {
+    id: DeviceId,
     path: String,
     dev: Device,
     enabled: bool,
     device_type: DeviceType,
+    // Sysfs path of the physical device, shared by all of its event devices
+    group: Option<PathBuf>,
+    nodes: Vec<InputDevice>,
     has_button_held: bool,
+    held_buttons: Vec<PadButton>,
+    // Key pressed during the last poll, for keyboards used as gamepads
+    pressed_key: Option<KeyCode>,
+    // D-pad position, and the left stick's position, range and flat zone, for navigating menus
+    hat: (i32, i32),
+    stick: (i32, i32),
+    stick_range: Option<StickRange>,
+    // Direction currently held, and when it next repeats
+    held_dir: Option<(PadButton, Instant)>,
+    // Uploaded the first time the device rumbles; the effect is erased when it's dropped
+    rumble: Option<FFEffect>,
 }
 impl InputDevice {
     pub fn name(&self) -> &str {
@@ -51,9 +189,11 @@ impl InputDevice {
     pub fn emoji(&self) -> &str {
         match self.device_type() {
             DeviceType::Gamepad => "🎮",
+            DeviceType::Joystick => "🕹",
+            DeviceType::Wheel => "🚗",
             DeviceType::Keyboard => "🖮",
-            DeviceType::Mouse => "🖱",
-            DeviceType::Other => "",
+            DeviceType::Mouse | DeviceType::Touchpad => "🖱",
+            DeviceType::Motion | DeviceType::Other => "",
         }
     }
     pub fn fancyname(&self) -> &str {
@@ -65,6 +205,9 @@ impl InputDevice {
             _ => self.name(),
         }
     }
+    pub fn id(&self) -> &DeviceId {
+        &self.id
+    }
     pub fn path(&self) -> &str {
         &self.path
     }
@@ -77,22 +220,133 @@ impl InputDevice {
     pub fn has_button_held(&self) -> bool {
         self.has_button_held
     }
+    pub fn pressed_key(&self) -> Option<KeyCode> {
+        self.pressed_key
+    }
+    pub fn is_held(&self, btn: PadButton) -> bool {
+        self.held_buttons.contains(&btn)
+    }
+    // Buzzes the device briefly so its owner can tell which one it is
+    pub fn rumble(&mut self) {
+        if !self
+            .dev
+            .supported_ff()
+            .is_some_and(|ff| ff.contains(FFEffectCode::FF_RUMBLE))
+        {
+            return;
+        }
+        if self.rumble.is_none() {
+            let effect = FFEffectData {
+                direction: 0,
+                trigger: FFTrigger::default(),
+                replay: FFReplay {
+                    length: 200,
+                    delay: 0,
+                },
+                kind: FFEffectKind::Rumble {
+                    strong_magnitude: 0x8000,
+                    weak_magnitude: 0xc000,
+                },
+            };
+            match self.dev.upload_ff_effect(effect) {
+                Ok(effect) => self.rumble = Some(effect),
+                Err(err) => {
+                    println!(
+                        "[partydeck] Couldn't upload rumble effect to {}: {}",
+                        self.path, err
+                    );
+                    return;
+                }
+            }
+        }
+        if let Some(effect) = &mut self.rumble
+            && let Err(err) = effect.play(1)
+        {
+            println!("[partydeck] Couldn't rumble {}: {}", self.path, err);
+        }
+    }
+    // Lights up the controller's player number LEDs, where the driver exposes them;
+    // player 0 turns them off. Writing to the LEDs usually needs a udev rule granting access.
+    pub fn set_player_leds(&self, player: usize) {
+        let Some(event) = Path::new(&self.path).file_name() else {
+            return;
+        };
+        // The LEDs belong to the HID or USB device that the input device sits under
+        let leds_dir = Path::new("/sys/class/input")
+            .join(event)
+            .join("device/device/leds");
+        let Ok(entries) = std::fs::read_dir(leds_dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let brightness = if let Some((_, n)) = name.rsplit_once(":player-") {
+                // hid-playstation, hid-nintendo and others: one LED per player number
+                match n.parse::<usize>() == Ok(player) {
+                    true => "1".to_string(),
+                    false => "0".to_string(),
+                }
+            } else if name.starts_with("xpad") {
+                // xpad: 6 to 9 light up the ring's quadrant for players 1 to 4
+                match player {
+                    1..=4 => (5 + player).to_string(),
+                    _ => "0".to_string(),
+                }
+            } else {
+                continue;
+            };
+            let _ = std::fs::write(entry.path().join("brightness"), brightness);
+        }
+    }
+    // Finds the device's other nodes through sysfs
+    fn sibling_nodes(&self) -> Vec<String> {
+        let mut out: Vec<String> = Vec::new();
+        let events = std::iter::once(self).chain(self.nodes.iter());
+        for dev in events {
+            if dev.path != self.path {
+                out.push(dev.path.clone());
+            }
+            // Legacy joystick devices sit next to the event device under the same input device
+            let Some(event) = Path::new(&dev.path).file_name() else {
+                continue;
+            };
+            let input_dir = Path::new("/sys/class/input").join(event).join("device");
+            if let Ok(entries) = std::fs::read_dir(input_dir) {
+                for entry in entries.flatten() {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    if name.starts_with("js") {
+                        out.push(format!("/dev/input/{name}"));
+                    }
+                }
+            }
+        }
+        if let Some(group) = &self.group {
+            find_hidraw(&physical_device(group), 0, &mut out);
+        }
+        out.sort();
+        out.dedup();
+        out
+    }
     pub fn info(&self) -> DeviceInfo {
         DeviceInfo {
+            id: self.id().clone(),
             path: self.path().to_string(),
+            nodes: self.sibling_nodes(),
             enabled: self.enabled(),
             device_type: self.device_type(),
         }
     }
     pub fn poll(&mut self) -> Option<PadButton> {
         let mut btn: Option<PadButton> = None;
+        self.pressed_key = None;
         if let Ok(events) = self.dev.fetch_events() {
             for event in events {
                 let summary = event.destructure();
 
                 match summary {
-                    EventSummary::Key(_, _, 1) => {
+                    EventSummary::Key(_, key, 1) => {
                         self.has_button_held = true;
+                        self.pressed_key = Some(key);
                     }
                     EventSummary::Key(_, _, 0) => {
                         self.has_button_held = false;
@@ -100,24 +354,36 @@ impl InputDevice {
                     _ => {}
                 }
 
-                btn = match summary {
-                    EventSummary::Key(_, KeyCode::BTN_SOUTH, 1) => Some(PadButton::ABtn),
-                    EventSummary::Key(_, KeyCode::BTN_EAST, 1) => Some(PadButton::BBtn),
-                    EventSummary::Key(_, KeyCode::BTN_NORTH, 1) => Some(PadButton::XBtn),
-                    EventSummary::Key(_, KeyCode::BTN_WEST, 1) => Some(PadButton::YBtn),
-                    EventSummary::Key(_, KeyCode::BTN_START, 1) => Some(PadButton::StartBtn),
-                    EventSummary::Key(_, KeyCode::BTN_SELECT, 1) => Some(PadButton::SelectBtn),
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, -1) => {
-                        Some(PadButton::Left)
+                // Keep track of held gamepad buttons for button combinations
+                if let EventSummary::Key(_, key, value) = summary
+                    && let Some(held) = gamepad_button(key)
+                {
+                    match value {
+                        1 if !self.held_buttons.contains(&held) => self.held_buttons.push(held),
+                        0 => self.held_buttons.retain(|b| *b != held),
+                        _ => {}
+                    }
+                }
+
+                match summary {
+                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, value) => {
+                        self.hat.0 = value
                     }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0X, 1) => {
-                        Some(PadButton::Right)
+                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, value) => {
+                        self.hat.1 = value
                     }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, -1) => {
-                        Some(PadButton::Up)
+                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_X, value) => {
+                        self.stick.0 = value
                     }
-                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_HAT0Y, 1) => {
-                        Some(PadButton::Down)
+                    EventSummary::AbsoluteAxis(_, AbsoluteAxisCode::ABS_Y, value) => {
+                        self.stick.1 = value
+                    }
+                    _ => {}
+                }
+
+                btn = match summary {
+                    EventSummary::Key(_, key, 1) if gamepad_button(key).is_some() => {
+                        gamepad_button(key)
                     }
                     //keyboard
                     EventSummary::Key(_, KeyCode::KEY_A, 1) => Some(PadButton::AKey),
@@ -130,58 +396,511 @@ impl InputDevice {
                 };
             }
         }
+
+        // Directions are sent when they're first pressed, then repeated while they're held
+        let now = Instant::now();
+        match (self.direction(), self.held_dir) {
+            (None, _) => self.held_dir = None,
+            (Some(dir), Some((held, _))) if dir == held => {}
+            (Some(dir), _) => {
+                self.held_dir = Some((dir, now + REPEAT_DELAY));
+                btn = btn.or(Some(dir));
+            }
+        }
+        if btn.is_none()
+            && let Some((dir, at)) = self.held_dir
+            && now >= at
+        {
+            self.held_dir = Some((dir, now + REPEAT_INTERVAL));
+            btn = Some(dir);
+        }
         btn
     }
+
+    // The direction held on the D-pad, or on the left stick outside of its deadzone
+    fn direction(&self) -> Option<PadButton> {
+        match self.hat {
+            (_, -1) => return Some(PadButton::Up),
+            (_, 1) => return Some(PadButton::Down),
+            (-1, _) => return Some(PadButton::Left),
+            (1, _) => return Some(PadButton::Right),
+            _ => {}
+        }
+
+        let StickRange { min, max, flat } = self.stick_range?;
+        let center = (min + max) / 2;
+        let (x, y) = (self.stick.0 - center, self.stick.1 - center);
+        let deadzone = (((max - min) as f32 * STICK_DEADZONE) as i32).max(flat);
+        if x.abs().max(y.abs()) < deadzone {
+            return None;
+        }
+        match y.abs() >= x.abs() {
+            true if y < 0 => Some(PadButton::Up),
+            true => Some(PadButton::Down),
+            false if x < 0 => Some(PadButton::Left),
+            false => Some(PadButton::Right),
+        }
+    }
 }
 
-pub fn scan_input_devices(filter: &PadFilterType) -> Vec<InputDevice> {
-    let mut pads: Vec<InputDevice> = Vec::new();
-    for dev in evdev::enumerate() {
-        let enabled = match filter {
-            PadFilterType::All => true,
-            PadFilterType::NoSteamInput => dev.1.input_id().vendor() != 0x28de,
-            PadFilterType::OnlySteamInput => dev.1.input_id().vendor() == 0x28de,
+fn gamepad_button(key: KeyCode) -> Option<PadButton> {
+    match key {
+        KeyCode::BTN_SOUTH => Some(PadButton::ABtn),
+        KeyCode::BTN_EAST => Some(PadButton::BBtn),
+        KeyCode::BTN_NORTH => Some(PadButton::XBtn),
+        KeyCode::BTN_WEST => Some(PadButton::YBtn),
+        KeyCode::BTN_START => Some(PadButton::StartBtn),
+        KeyCode::BTN_SELECT => Some(PadButton::SelectBtn),
+        KeyCode::BTN_TL => Some(PadButton::LBBtn),
+        KeyCode::BTN_TR => Some(PadButton::RBBtn),
+        // Generic joystick buttons, as used by most arcade sticks and flight sticks
+        KeyCode::BTN_TRIGGER => Some(PadButton::ABtn),
+        KeyCode::BTN_THUMB => Some(PadButton::BBtn),
+        KeyCode::BTN_THUMB2 => Some(PadButton::XBtn),
+        KeyCode::BTN_TOP => Some(PadButton::YBtn),
+        KeyCode::BTN_BASE4 => Some(PadButton::StartBtn),
+        KeyCode::BTN_BASE3 => Some(PadButton::SelectBtn),
+        _ => None,
+    }
+}
+
+// Maps event device paths to the name of their udev symlink, preferring by-path over by-id
+// since identical controllers without serial numbers all get the same by-id name
+fn input_links() -> HashMap<PathBuf, String> {
+    let mut links: HashMap<PathBuf, String> = HashMap::new();
+    for dir in ["/dev/input/by-id", "/dev/input/by-path"] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
         };
+        for entry in entries.flatten() {
+            if let Ok(target) = entry.path().canonicalize() {
+                links.insert(target, entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    links
+}
 
-        let device_type = if dev
-            .1
-            .supported_keys()
-            .map_or(false, |keys| keys.contains(KeyCode::BTN_SOUTH))
-        {
-            DeviceType::Gamepad
-        } else if dev
-            .1
-            .supported_keys()
-            .map_or(false, |keys| keys.contains(KeyCode::BTN_LEFT))
-        {
-            DeviceType::Mouse
-        } else if dev
-            .1
-            .supported_keys()
-            .map_or(false, |keys| keys.contains(KeyCode::KEY_SPACE))
-        {
-            DeviceType::Keyboard
+fn device_id(dev: &Device, path: &Path, links: &HashMap<PathBuf, String>) -> DeviceId {
+    let input_id = dev.input_id();
+    DeviceId {
+        vendor: input_id.vendor(),
+        product: input_id.product(),
+        version: input_id.version(),
+        name: dev.name().unwrap_or_default().to_string(),
+        uniq: dev.unique_name().unwrap_or_default().to_string(),
+        phys: dev.physical_path().unwrap_or_default().to_string(),
+        link: links.get(path).cloned().unwrap_or_default(),
+        index: 0,
+    }
+}
+
+pub fn find_device(devices: &[InputDevice], id: &DeviceId) -> Option<usize> {
+    devices.iter().position(|dev| dev.id() == id)
+}
+
+// Returns the device's identity, numbered after any identical devices already in the list
+fn unique_id(devices: &[InputDevice], mut id: DeviceId) -> DeviceId {
+    while devices.iter().any(|dev| dev.id == id) {
+        id.index += 1;
+    }
+    id
+}
+
+// Reads the ID_INPUT_* properties udev has set for an event device
+fn udev_input_properties(path: &Path) -> Vec<String> {
+    let Some(event) = path.file_name() else {
+        return Vec::new();
+    };
+    let Ok(devnum) = std::fs::read_to_string(Path::new("/sys/class/input").join(event).join("dev"))
+    else {
+        return Vec::new();
+    };
+    let Ok(data) = std::fs::read_to_string(format!("/run/udev/data/c{}", devnum.trim())) else {
+        return Vec::new();
+    };
+    data.lines()
+        .filter_map(|line| line.strip_prefix("E:"))
+        .filter(|prop| prop.starts_with("ID_INPUT_") && prop.ends_with("=1"))
+        .map(|prop| prop.trim_end_matches("=1").to_string())
+        .collect()
+}
+
+fn classify_device(dev: &Device, path: &Path) -> DeviceType {
+    let udev = udev_input_properties(path);
+    let has_udev = |prop: &str| udev.iter().any(|p| p == prop);
+    let has_key = |key: KeyCode| dev.supported_keys().is_some_and(|keys| keys.contains(key));
+    let has_abs = |axis: AbsoluteAxisCode| {
+        dev.supported_absolute_axes()
+            .is_some_and(|axes| axes.contains(axis))
+    };
+    let has_prop = |prop: PropType| dev.properties().contains(prop);
+
+    if has_prop(PropType::ACCELEROMETER) || has_udev("ID_INPUT_ACCELEROMETER") {
+        DeviceType::Motion
+    } else if has_udev("ID_INPUT_TOUCHPAD")
+        || (has_prop(PropType::POINTER) && has_abs(AbsoluteAxisCode::ABS_MT_POSITION_X))
+        || has_prop(PropType::BUTTONPAD)
+    {
+        DeviceType::Touchpad
+    } else if has_key(KeyCode::BTN_SOUTH) {
+        DeviceType::Gamepad
+    } else if has_key(KeyCode::BTN_GEAR_DOWN)
+        || has_abs(AbsoluteAxisCode::ABS_WHEEL)
+        || (has_abs(AbsoluteAxisCode::ABS_GAS) && has_abs(AbsoluteAxisCode::ABS_BRAKE))
+    {
+        DeviceType::Wheel
+    } else if has_key(KeyCode::BTN_TRIGGER)
+        || (has_key(KeyCode::BTN_THUMB) && has_abs(AbsoluteAxisCode::ABS_X))
+        || has_udev("ID_INPUT_JOYSTICK")
+    {
+        DeviceType::Joystick
+    } else if has_key(KeyCode::BTN_LEFT) || has_udev("ID_INPUT_MOUSE") {
+        DeviceType::Mouse
+    } else if has_key(KeyCode::KEY_SPACE) || has_udev("ID_INPUT_KEYBOARD") {
+        DeviceType::Keyboard
+    } else {
+        DeviceType::Other
+    }
+}
+
+// The HID or USB interface that an event device belongs to. Virtual devices, such as
+// Steam Input's gamepads, each stand on their own.
+fn device_group(path: &Path) -> Option<PathBuf> {
+    let event = path.file_name()?;
+    let group = Path::new("/sys/class/input")
+        .join(event)
+        .join("device/device")
+        .canonicalize()
+        .ok()?;
+    match group.starts_with("/sys/devices/virtual") {
+        true => None,
+        false => Some(group),
+    }
+}
+
+// The sysfs directory of the whole physical device: the USB device for USB controllers, which
+// may expose several HID interfaces, or the HID device itself otherwise (e.g. over Bluetooth)
+fn physical_device(group: &Path) -> PathBuf {
+    for dir in group.ancestors().take(3) {
+        if dir.join("idVendor").exists() {
+            return dir.to_path_buf();
+        }
+    }
+    group.to_path_buf()
+}
+
+// Collects the hidraw device nodes found anywhere under a sysfs device directory
+fn find_hidraw(dir: &Path, depth: usize, out: &mut Vec<String>) {
+    if depth > 4 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        // Symlinks (driver, subsystem...) lead out of the device, so they aren't followed
+        if !entry.file_type().is_ok_and(|t| t.is_dir()) {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("hidraw") && name != "hidraw" {
+            out.push(format!("/dev/{name}"));
         } else {
-            DeviceType::Other
+            find_hidraw(&entry.path(), depth + 1, out);
+        }
+    }
+}
+
+// PartyDeck's own virtual gamepads aren't devices that can be assigned
+fn is_virtual_pad(dev: &Device) -> bool {
+    dev.name()
+        .is_some_and(|name| name.starts_with(VIRTUAL_PAD_NAME))
+}
+
+// Range of the left stick's X axis, and the larger of the X and Y axes' flat zones
+#[derive(Clone, Copy)]
+struct StickRange {
+    min: i32,
+    max: i32,
+    flat: i32,
+}
+
+// Current position of the X and Y axes, and their range
+fn left_stick(dev: &Device) -> ((i32, i32), Option<StickRange>) {
+    let Ok(absinfo) = dev.get_absinfo() else {
+        return ((0, 0), None);
+    };
+    let absinfo: Vec<(AbsoluteAxisCode, AbsInfo)> = absinfo.collect();
+    let axis = |code: AbsoluteAxisCode| absinfo.iter().find(|(a, _)| *a == code).map(|(_, i)| *i);
+    match (axis(AbsoluteAxisCode::ABS_X), axis(AbsoluteAxisCode::ABS_Y)) {
+        (Some(x), Some(y)) if x.maximum() > x.minimum() => {
+            let range = StickRange {
+                min: x.minimum(),
+                max: x.maximum(),
+                flat: x.flat().max(y.flat()),
+            };
+            ((x.value(), y.value()), Some(range))
+        }
+        _ => ((0, 0), None),
+    }
+}
+
+fn open_input_device(
+    path: PathBuf,
+    dev: Device,
+    filter: &PadFilterType,
+    links: &HashMap<PathBuf, String>,
+) -> Option<InputDevice> {
+    let enabled = match filter {
+        PadFilterType::All => true,
+        PadFilterType::NoSteamInput => dev.input_id().vendor() != 0x28de,
+        PadFilterType::OnlySteamInput => dev.input_id().vendor() == 0x28de,
+    };
+
+    if dev.set_nonblocking(true).is_err() {
+        println!(
+            "[partydeck] evdev: Failed to set non-blocking mode for {}",
+            path.display()
+        );
+        return None;
+    }
+    let device_type = classify_device(&dev, &path);
+    // The left stick of gamepads and joysticks can navigate menus; a wheel's X axis is its wheel
+    let (stick, stick_range) = match device_type {
+        DeviceType::Gamepad | DeviceType::Joystick => left_stick(&dev),
+        _ => ((0, 0), None),
+    };
+    Some(InputDevice {
+        id: device_id(&dev, &path, links),
+        device_type,
+        group: device_group(&path),
+        path: path.to_str().unwrap().to_string(),
+        dev,
+        enabled,
+        nodes: Vec::new(),
+        has_button_held: false,
+        pressed_key: None,
+        hat: (0, 0),
+        stick,
+        stick_range,
+        held_dir: None,
+        held_buttons: Vec::new(),
+        rumble: None,
+    })
+}
+
+// Puts the event devices of each physical device together: a controller takes all of its
+// other nodes (touchpad, motion sensors...), while a keyboard or mouse takes the nodes that
+// aren't usable on their own, such as media keys. Nodes that are left over are dropped.
+fn group_devices(devices: Vec<InputDevice>) -> Vec<InputDevice> {
+    let mut groups: Vec<Vec<InputDevice>> = Vec::new();
+    for dev in devices {
+        match groups
+            .iter_mut()
+            .find(|g| dev.group.is_some() && g[0].group == dev.group)
+        {
+            Some(group) => group.push(dev),
+            None => groups.push(vec![dev]),
+        }
+    }
+
+    let mut out: Vec<InputDevice> = Vec::new();
+    for mut group in groups {
+        group.sort_by_key(|dev| std::cmp::Reverse(dev.device_type.priority()));
+        let mut primaries: Vec<InputDevice> = Vec::new();
+        let mut rest: Vec<InputDevice> = Vec::new();
+        for mut dev in group {
+            let standalone = match primaries.first() {
+                Some(first) if first.device_type.is_controller() => false,
+                _ => dev.device_type.priority() >= DeviceType::Touchpad.priority(),
+            };
+            if standalone {
+                if dev.device_type == DeviceType::Touchpad {
+                    dev.device_type = DeviceType::Mouse;
+                }
+                primaries.push(dev);
+            } else {
+                rest.push(dev);
+            }
+        }
+        match primaries.first_mut() {
+            Some(first) => first.nodes.extend(rest),
+            None => continue,
+        }
+        out.extend(primaries);
+    }
+    out
+}
+
+// Takes a device and its grouped nodes apart again, so they can be regrouped
+fn ungroup_device(mut dev: InputDevice) -> Vec<InputDevice> {
+    let mut out: Vec<InputDevice> = std::mem::take(&mut dev.nodes);
+    out.push(dev);
+    out
+}
+
+// Opens a single device node on its own, without grouping or filtering
+pub fn open_input_path(path: &str) -> Option<InputDevice> {
+    let dev = Device::open(path).ok()?;
+    open_input_device(
+        PathBuf::from(path),
+        dev,
+        &PadFilterType::All,
+        &input_links(),
+    )
+}
+
+pub fn scan_input_devices(filter: &PadFilterType) -> Vec<InputDevice> {
+    let links = input_links();
+    let mut nodes: Vec<InputDevice> = Vec::new();
+    for (path, dev) in evdev::enumerate() {
+        if is_virtual_pad(&dev) {
+            continue;
+        }
+        if let Some(node) = open_input_device(path, dev, filter, &links) {
+            nodes.push(node);
+        }
+    }
+    nodes.sort_by_key(|node| node.path().to_string());
+
+    let mut pads = group_devices(nodes);
+    pads.sort_by_key(|pad| pad.path().to_string());
+
+    // Number the devices that would otherwise share an identity, in event path order
+    for i in 1..pads.len() {
+        pads[i].id = unique_id(&pads[..i], pads[i].id.clone());
+    }
+    pads
+}
+
+// Watches /dev/input so devices can be added and removed as they're plugged in and out
+pub struct DeviceWatcher {
+    inotify: Option<Inotify>,
+}
+
+impl DeviceWatcher {
+    pub fn new() -> Self {
+        let inotify =
+            Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC).and_then(|inotify| {
+                // Device nodes are created as root and only become readable once udev sets
+                // their permissions, so attribute changes are watched too
+                inotify.add_watch(
+                    "/dev/input",
+                    AddWatchFlags::IN_CREATE | AddWatchFlags::IN_DELETE | AddWatchFlags::IN_ATTRIB,
+                )?;
+                Ok(inotify)
+            });
+        match inotify {
+            Ok(inotify) => Self {
+                inotify: Some(inotify),
+            },
+            Err(err) => {
+                println!(
+                    "[partydeck] Can't watch /dev/input for new devices: {}",
+                    err
+                );
+                Self { inotify: None }
+            }
+        }
+    }
+
+    // Returns the event device paths that changed since the last call, without blocking
+    pub fn changed_paths(&self) -> Vec<PathBuf> {
+        let mut out: Vec<PathBuf> = Vec::new();
+        let Some(inotify) = &self.inotify else {
+            return out;
         };
+        while let Ok(events) = inotify.read_events() {
+            for event in events {
+                let Some(name) = event.name else {
+                    continue;
+                };
+                if !name.to_string_lossy().starts_with("event") {
+                    continue;
+                }
+                let path = Path::new("/dev/input").join(name);
+                if !out.contains(&path) {
+                    out.push(path);
+                }
+            }
+        }
+        out
+    }
+}
 
-        if device_type != DeviceType::Other {
-            if dev.1.set_nonblocking(true).is_err() {
+// Adds the devices that appeared and removes the ones that are gone, leaving the rest untouched.
+// Returns true if the list changed.
+pub fn update_input_devices(
+    devices: &mut Vec<InputDevice>,
+    changed: &[PathBuf],
+    filter: &PadFilterType,
+) -> bool {
+    let mut updated = false;
+    let links = input_links();
+    let mut added: Vec<InputDevice> = Vec::new();
+    for path in changed {
+        let known = devices.iter().position(|dev| Path::new(dev.path()) == path);
+        let known_node = devices
+            .iter()
+            .any(|dev| dev.nodes.iter().any(|node| Path::new(node.path()) == path));
+        match (known, known_node, path.exists()) {
+            (Some(d), _, false) => {
                 println!(
-                    "[partydeck] evdev: Failed to set non-blocking mode for {}",
-                    dev.0.display()
+                    "[partydeck] Device removed: {} ({})",
+                    devices[d].name(),
+                    path.display()
                 );
-                continue;
+                devices.remove(d);
+                updated = true;
             }
-            pads.push(InputDevice {
-                path: dev.0.to_str().unwrap().to_string(),
-                dev: dev.1,
-                enabled,
-                device_type,
-                has_button_held: false,
-            });
+            (None, true, false) => {
+                for dev in devices.iter_mut() {
+                    dev.nodes.retain(|node| Path::new(node.path()) != path);
+                }
+            }
+            (None, false, true) => {
+                // Fails until udev has given the node its permissions; it's tried again then
+                let Ok(dev) = Device::open(path) else {
+                    continue;
+                };
+                if is_virtual_pad(&dev) {
+                    continue;
+                }
+                if let Some(node) = open_input_device(path.clone(), dev, filter, &links) {
+                    added.push(node);
+                }
+            }
+            _ => {}
         }
     }
-    pads.sort_by_key(|pad| pad.path().to_string());
-    pads
+
+    if !added.is_empty() {
+        // Devices that new nodes belong to are regrouped along with them
+        let mut regrouped: Vec<String> = Vec::new();
+        let mut i = 0;
+        while i < devices.len() {
+            if devices[i].group.is_some() && added.iter().any(|n| n.group == devices[i].group) {
+                regrouped.push(devices[i].path.clone());
+                added.extend(ungroup_device(devices.remove(i)));
+            } else {
+                i += 1;
+            }
+        }
+        added.sort_by_key(|node| node.path().to_string());
+
+        for mut pad in group_devices(added) {
+            if !regrouped.contains(&pad.path) {
+                println!("[partydeck] Device added: {} ({})", pad.name(), pad.path());
+            }
+            pad.id = unique_id(devices, pad.id.clone());
+            devices.push(pad);
+            updated = true;
+        }
+    }
+
+    if updated {
+        devices.sort_by_key(|pad| pad.path().to_string());
+    }
+    updated
 }
@@ -1,50 +1,48 @@
 use crate::Monitor;
 use crate::app::PartyConfig;
+use crate::input::DeviceId;
+use crate::layout::*;
 use crate::profiles::GUEST_NAMES;
 
 #[derive(Clone)]
 pub struct Instance {
-    pub devices: Vec<usize>,
+    pub devices: Vec<DeviceId>,
+    // Keymap the instance's keyboard is used as a gamepad with
+    pub keymap: Option<usize>,
     pub profname: String,
     pub profselection: usize,
     pub monitor: usize,
     pub width: u32,
     pub height: u32,
+    // Area on the monitor according to the layout
+    pub rect: Rect,
+}
+
+impl Instance {
+    // Whether the instance's keyboard is turned into a gamepad instead of being used as a keyboard
+    pub fn has_keyboard_gamepad(&self, cfg: &PartyConfig) -> bool {
+        self.keymap.is_some() && cfg.virtual_gamepads && cfg.keyboard_gamepads
+    }
 }
 
 pub fn set_instance_resolutions(
-    instances: &mut Vec<Instance>,
+    instances: &mut [Instance],
     primary_monitor: &Monitor,
+    layout: Option<&CustomLayout>,
     cfg: &PartyConfig,
 ) {
-    let (basewidth, baseheight) = (primary_monitor.width(), primary_monitor.height());
-    let playercount = instances.len();
+    let layout = layout_for(instances.len(), layout, cfg);
 
-    for instance in instances {
-        let (mut w, mut h) = match playercount {
-            1 => (basewidth, baseheight),
-            2 => {
-                if cfg.vertical_two_player {
-                    (basewidth / 2, baseheight)
-                } else {
-                    (basewidth, baseheight / 2)
-                }
-            }
-            _ => (basewidth / 2, baseheight / 2),
-        };
-        if h < 600 && cfg.gamescope_fix_lowres {
-            let ratio = w as f32 / h as f32;
-            h = 600;
-            w = (h as f32 * ratio) as u32;
-        }
-        instance.width = w;
-        instance.height = h;
+    for (i, instance) in instances.iter_mut().enumerate() {
+        instance.rect = layout[i];
+        (instance.width, instance.height) = instance_resolution(&layout[i], primary_monitor, cfg);
     }
 }
 
 pub fn set_instance_resolutions_multimonitor(
-    instances: &mut Vec<Instance>,
+    instances: &mut [Instance],
     monitors: &Vec<Monitor>,
+    layout: Option<&CustomLayout>,
     cfg: &PartyConfig,
 ) {
     let mut mon_playercounts: Vec<usize> = vec![0; monitors.len()];
@@ -52,33 +50,45 @@ pub fn set_instance_resolutions_multimonitor(
         let mon = instance.monitor;
         mon_playercounts[mon] += 1;
     }
+    let layouts: Vec<Vec<Rect>> = mon_playercounts
+        .iter()
+        .map(|&count| layout_for(count, layout, cfg))
+        .collect();
 
+    let mut mon_indices: Vec<usize> = vec![0; monitors.len()];
     for instance in instances.iter_mut() {
-        let playercount = mon_playercounts[instance.monitor];
-        let (basewidth, baseheight) = (
-            monitors[instance.monitor].width(),
-            monitors[instance.monitor].height(),
-        );
+        let mon = instance.monitor;
+        let rect = &layouts[mon][mon_indices[mon]];
+        mon_indices[mon] += 1;
+        instance.rect = *rect;
+        (instance.width, instance.height) = instance_resolution(rect, &monitors[mon], cfg);
+    }
+}
 
-        let (mut w, mut h) = match playercount {
-            1 => (basewidth, baseheight),
-            2 => {
-                if cfg.vertical_two_player {
-                    (basewidth / 2, baseheight)
-                } else {
-                    (basewidth, baseheight / 2)
-                }
-            }
-            _ => (basewidth / 2, baseheight / 2),
-        };
-        if h < 600 && cfg.gamescope_fix_lowres {
-            let ratio = w as f32 / h as f32;
-            h = 600;
-            w = (h as f32 * ratio) as u32;
-        }
-        instance.width = w;
-        instance.height = h;
+fn instance_resolution(rect: &Rect, monitor: &Monitor, cfg: &PartyConfig) -> (u32, u32) {
+    let (_, _, mut w, mut h) = rect.to_pixels(monitor.width(), monitor.height());
+    if h < 600 && cfg.gamescope_fix_lowres {
+        let ratio = w as f32 / h as f32;
+        h = 600;
+        w = (h as f32 * ratio) as u32;
+    }
+    (w, h)
+}
+
+// Sets resolutions and profile names right before launching; shared by the GUI and CLI launchers
+pub fn prepare_instances(
+    instances: &mut Vec<Instance>,
+    monitors: &Vec<Monitor>,
+    profiles: &[String],
+    cfg: &PartyConfig,
+) {
+    let layout = load_layout(&cfg.layout);
+    if cfg.gamescope_sdl_backend {
+        set_instance_resolutions_multimonitor(instances, monitors, layout.as_ref(), cfg);
+    } else {
+        set_instance_resolutions(instances, &monitors[0], layout.as_ref(), cfg);
     }
+    set_instance_names(instances, profiles);
 }
 
 pub fn set_instance_names(instances: &mut Vec<Instance>, profiles: &[String]) {
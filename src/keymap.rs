@@ -0,0 +1,335 @@
+use crate::paths::PATH_PARTY;
+
+use evdev::{AbsInfo, AbsoluteAxisCode, EventType, InputEvent, KeyCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// Turns keyboard keys into gamepad input, so a keyboard (or half of one) can be used as a
+// controller. Keys, buttons and axes are given by their evdev names.
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Keymap {
+    pub name: String,
+    // Keyboard key to gamepad button, e.g. "KEY_SPACE": "BTN_SOUTH"
+    pub buttons: BTreeMap<String, String>,
+    // Keyboard key to one end of a gamepad axis, e.g. "KEY_W": ["ABS_Y", -1]
+    pub axes: BTreeMap<String, (String, i32)>,
+}
+
+fn keymap(name: &str, buttons: &[(&str, &str)], axes: &[(&str, &str, i32)]) -> Keymap {
+    Keymap {
+        name: name.to_string(),
+        buttons: buttons
+            .iter()
+            .map(|(key, btn)| (key.to_string(), btn.to_string()))
+            .collect(),
+        axes: axes
+            .iter()
+            .map(|(key, axis, dir)| (key.to_string(), (axis.to_string(), *dir)))
+            .collect(),
+    }
+}
+
+// Two players sharing one keyboard: one on the WASD side, one on the arrow side
+pub fn default_keymaps() -> Vec<Keymap> {
+    vec![
+        keymap(
+            "Left half (WASD)",
+            &[
+                ("KEY_SPACE", "BTN_SOUTH"),
+                ("KEY_LEFTSHIFT", "BTN_EAST"),
+                ("KEY_F", "BTN_WEST"),
+                ("KEY_R", "BTN_NORTH"),
+                ("KEY_Q", "BTN_TL"),
+                ("KEY_E", "BTN_TR"),
+                ("KEY_C", "BTN_THUMBL"),
+                ("KEY_TAB", "BTN_SELECT"),
+                ("KEY_ESC", "BTN_START"),
+            ],
+            &[
+                ("KEY_W", "ABS_Y", -1),
+                ("KEY_S", "ABS_Y", 1),
+                ("KEY_A", "ABS_X", -1),
+                ("KEY_D", "ABS_X", 1),
+                ("KEY_1", "ABS_Z", 1),
+                ("KEY_3", "ABS_RZ", 1),
+            ],
+        ),
+        keymap(
+            "Right half (arrows)",
+            &[
+                ("KEY_SLASH", "BTN_SOUTH"),
+                ("KEY_RIGHTSHIFT", "BTN_EAST"),
+                ("KEY_DOT", "BTN_WEST"),
+                ("KEY_SEMICOLON", "BTN_NORTH"),
+                ("KEY_L", "BTN_TL"),
+                ("KEY_APOSTROPHE", "BTN_TR"),
+                ("KEY_COMMA", "BTN_THUMBL"),
+                ("KEY_BACKSPACE", "BTN_SELECT"),
+                ("KEY_ENTER", "BTN_START"),
+            ],
+            &[
+                ("KEY_UP", "ABS_Y", -1),
+                ("KEY_DOWN", "ABS_Y", 1),
+                ("KEY_LEFT", "ABS_X", -1),
+                ("KEY_RIGHT", "ABS_X", 1),
+                ("KEY_O", "ABS_Z", 1),
+                ("KEY_LEFTBRACE", "ABS_RZ", 1),
+            ],
+        ),
+    ]
+}
+
+pub fn keymaps_path() -> PathBuf {
+    PATH_PARTY.join("keymaps.json")
+}
+
+// Loads the keymaps, writing out the defaults first so they can be edited
+pub fn load_keymaps() -> Vec<Keymap> {
+    let Ok(file) = File::open(keymaps_path()) else {
+        let keymaps = default_keymaps();
+        if let Err(err) = save_keymaps(&keymaps) {
+            println!("[partydeck] Couldn't write keymaps: {}", err);
+        }
+        return keymaps;
+    };
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(keymaps) => keymaps,
+        Err(err) => {
+            println!("[partydeck] Couldn't read keymaps: {}", err);
+            default_keymaps()
+        }
+    }
+}
+
+pub fn save_keymaps(keymaps: &[Keymap]) -> Result<(), Box<dyn Error>> {
+    let file = File::create(keymaps_path())?;
+    serde_json::to_writer_pretty(file, keymaps)?;
+    Ok(())
+}
+
+impl Keymap {
+    // The first key bound to a gamepad button; used to join and leave with a keyboard half
+    pub fn key_for(&self, button: KeyCode) -> Option<KeyCode> {
+        self.buttons
+            .iter()
+            .filter(|(_, btn)| KeyCode::from_str(btn).is_ok_and(|btn| btn == button))
+            .find_map(|(key, _)| KeyCode::from_str(key).ok())
+    }
+
+    pub fn bindings(&self) -> Result<KeyBindings, Box<dyn Error>> {
+        let mut bindings: Vec<(KeyCode, KeyOutput)> = Vec::new();
+        for (key, btn) in &self.buttons {
+            let btn = KeyCode::from_str(btn)
+                .map_err(|_| format!("{}: unknown gamepad button {}", self.name, btn))?;
+            bindings.push((parse_key(&self.name, key)?, KeyOutput::Button(btn)));
+        }
+        for (key, (axis, dir)) in &self.axes {
+            let axis = AbsoluteAxisCode::from_str(axis)
+                .map_err(|_| format!("{}: unknown gamepad axis {}", self.name, axis))?;
+            bindings.push((
+                parse_key(&self.name, key)?,
+                KeyOutput::Axis(axis, dir.signum()),
+            ));
+        }
+        Ok(KeyBindings {
+            bindings,
+            held: Vec::new(),
+        })
+    }
+}
+
+// Short name of a key for the UI, e.g. SPACE for KEY_SPACE
+pub fn key_name(key: KeyCode) -> String {
+    let name = format!("{:?}", key);
+    name.trim_start_matches("KEY_").to_string()
+}
+
+fn parse_key(keymap: &str, key: &str) -> Result<KeyCode, Box<dyn Error>> {
+    Ok(KeyCode::from_str(key).map_err(|_| format!("{}: unknown key {}", keymap, key))?)
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum KeyOutput {
+    Button(KeyCode),
+    Axis(AbsoluteAxisCode, i32),
+}
+
+// Axis ranges of an Xbox 360 controller, which is what keyboard gamepads present themselves as
+fn axis_range(axis: AbsoluteAxisCode) -> (i32, i32) {
+    match axis {
+        AbsoluteAxisCode::ABS_Z | AbsoluteAxisCode::ABS_RZ => (0, 255),
+        AbsoluteAxisCode::ABS_HAT0X | AbsoluteAxisCode::ABS_HAT0Y => (-1, 1),
+        _ => (-32768, 32767),
+    }
+}
+
+// A keymap ready to translate one keyboard's key presses
+pub struct KeyBindings {
+    bindings: Vec<(KeyCode, KeyOutput)>,
+    held: Vec<KeyCode>,
+}
+
+impl KeyBindings {
+    pub fn buttons(&self) -> Vec<KeyCode> {
+        self.bindings
+            .iter()
+            .filter_map(|(_, output)| match output {
+                KeyOutput::Button(btn) => Some(*btn),
+                KeyOutput::Axis(..) => None,
+            })
+            .collect()
+    }
+
+    pub fn axes(&self) -> Vec<(AbsoluteAxisCode, AbsInfo)> {
+        let mut axes: Vec<AbsoluteAxisCode> = Vec::new();
+        for (_, output) in &self.bindings {
+            if let KeyOutput::Axis(axis, _) = output
+                && !axes.contains(axis)
+            {
+                axes.push(*axis);
+            }
+        }
+        axes.into_iter()
+            .map(|axis| {
+                let (min, max) = axis_range(axis);
+                (axis, AbsInfo::new(0, min, max, 0, 0, 0))
+            })
+            .collect()
+    }
+
+    // Gamepad events for a key being pressed (1) or released (0); repeats are ignored
+    pub fn translate(&mut self, key: KeyCode, value: i32) -> Vec<InputEvent> {
+        match value {
+            1 if !self.held.contains(&key) => self.held.push(key),
+            0 => self.held.retain(|k| *k != key),
+            _ => return Vec::new(),
+        }
+
+        let mut events: Vec<InputEvent> = Vec::new();
+        for (_, output) in self.bindings.iter().filter(|(k, _)| *k == key) {
+            match *output {
+                KeyOutput::Button(btn) => {
+                    events.push(InputEvent::new(EventType::KEY.0, btn.0, value));
+                }
+                KeyOutput::Axis(axis, _) => {
+                    let held = |dir: i32| {
+                        self.bindings.iter().any(|(k, output)| {
+                            *output == KeyOutput::Axis(axis, dir) && self.held.contains(k)
+                        })
+                    };
+                    let (min, max) = axis_range(axis);
+                    let value = match (held(-1), held(1)) {
+                        (true, false) => min,
+                        (false, true) => max,
+                        _ => min.max(0),
+                    };
+                    events.push(InputEvent::new(EventType::ABSOLUTE.0, axis.0, value));
+                }
+            }
+        }
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn left_half() -> KeyBindings {
+        default_keymaps()[0].bindings().unwrap()
+    }
+
+    fn events(events: Vec<InputEvent>) -> Vec<(u16, u16, i32)> {
+        events
+            .into_iter()
+            .map(|ev| (ev.event_type().0, ev.code(), ev.value()))
+            .collect()
+    }
+
+    fn button(btn: KeyCode, value: i32) -> (u16, u16, i32) {
+        (EventType::KEY.0, btn.0, value)
+    }
+
+    fn axis(axis: AbsoluteAxisCode, value: i32) -> (u16, u16, i32) {
+        (EventType::ABSOLUTE.0, axis.0, value)
+    }
+
+    #[test]
+    fn buttons() {
+        let mut bindings = left_half();
+        assert_eq!(
+            events(bindings.translate(KeyCode::KEY_SPACE, 1)),
+            [button(KeyCode::BTN_SOUTH, 1)]
+        );
+        // Key repeats don't press the button again
+        assert!(bindings.translate(KeyCode::KEY_SPACE, 2).is_empty());
+        assert!(bindings.translate(KeyCode::KEY_SPACE, 1).is_empty());
+        assert_eq!(
+            events(bindings.translate(KeyCode::KEY_SPACE, 0)),
+            [button(KeyCode::BTN_SOUTH, 0)]
+        );
+        assert!(bindings.translate(KeyCode::KEY_P, 1).is_empty());
+    }
+
+    #[test]
+    fn opposite_keys_cancel_out() {
+        let mut bindings = left_half();
+        let y = AbsoluteAxisCode::ABS_Y;
+        assert_eq!(
+            events(bindings.translate(KeyCode::KEY_W, 1)),
+            [axis(y, -32768)]
+        );
+        assert_eq!(events(bindings.translate(KeyCode::KEY_S, 1)), [axis(y, 0)]);
+        assert_eq!(
+            events(bindings.translate(KeyCode::KEY_W, 0)),
+            [axis(y, 32767)]
+        );
+        assert_eq!(events(bindings.translate(KeyCode::KEY_S, 0)), [axis(y, 0)]);
+    }
+
+    #[test]
+    fn triggers() {
+        let mut bindings = left_half();
+        let z = AbsoluteAxisCode::ABS_Z;
+        assert_eq!(
+            events(bindings.translate(KeyCode::KEY_1, 1)),
+            [axis(z, 255)]
+        );
+        assert_eq!(events(bindings.translate(KeyCode::KEY_1, 0)), [axis(z, 0)]);
+    }
+
+    #[test]
+    fn join_and_leave_keys() {
+        let keymaps = default_keymaps();
+        assert_eq!(
+            keymaps[0].key_for(KeyCode::BTN_SOUTH),
+            Some(KeyCode::KEY_SPACE)
+        );
+        assert_eq!(
+            keymaps[1].key_for(KeyCode::BTN_SOUTH),
+            Some(KeyCode::KEY_SLASH)
+        );
+        assert_eq!(
+            keymaps[1].key_for(KeyCode::BTN_EAST),
+            Some(KeyCode::KEY_RIGHTSHIFT)
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        let keymap = keymap("Broken", &[("KEY_NOPE", "BTN_SOUTH")], &[]);
+        assert!(keymap.bindings().is_err());
+        let keymap = keymap_with_axis("KEY_W", "ABS_NOPE");
+        assert!(keymap.bindings().is_err());
+    }
+
+    fn keymap_with_axis(key: &str, axis: &str) -> Keymap {
+        keymap("Broken", &[], &[(key, axis, 1)])
+    }
+}
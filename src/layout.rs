@@ -0,0 +1,215 @@
+use crate::app::PartyConfig;
+use crate::paths::*;
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+// A player's area on a monitor, with coordinates normalized to the monitor's size (0.0 to 1.0)
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub const FULL: Rect = Rect {
+        x: 0.0,
+        y: 0.0,
+        w: 1.0,
+        h: 1.0,
+    };
+
+    // Whether the rectangle has a size and lies within the monitor. NaN fails every comparison.
+    pub fn is_valid(self) -> bool {
+        // Allows for rounding in sizes like 1/3 that add up to slightly more than 1
+        const EPSILON: f32 = 1e-4;
+        self.x >= 0.0
+            && self.y >= 0.0
+            && self.w > 0.0
+            && self.h > 0.0
+            && self.x + self.w <= 1.0 + EPSILON
+            && self.y + self.h <= 1.0 + EPSILON
+    }
+
+    // Returns the rectangle in pixels for a monitor of the given size
+    pub fn to_pixels(self, width: u32, height: u32) -> (i32, i32, u32, u32) {
+        let (width, height) = (width as f32, height as f32);
+        (
+            (self.x * width).round() as i32,
+            (self.y * height).round() as i32,
+            (self.w * width).round() as u32,
+            (self.h * height).round() as u32,
+        )
+    }
+}
+
+// Splits a monitor into a grid for the given number of players. Rows are filled as evenly as
+// possible, with the rows that have fewer players on top: 3 players get one on top and two below,
+// 5 players get two on top and three below, and so on.
+pub fn grid_layout(playercount: usize, vertical_two_player: bool) -> Vec<Rect> {
+    if playercount <= 1 {
+        return vec![Rect::FULL];
+    }
+
+    let rows = match playercount {
+        2 if vertical_two_player => 1,
+        2 => 2,
+        _ => (playercount as f32 / 2.0).sqrt().ceil() as usize,
+    };
+    let base = playercount / rows;
+    let extra = playercount % rows;
+
+    let mut out = Vec::with_capacity(playercount);
+    let h = 1.0 / rows as f32;
+    for row in 0..rows {
+        let cols = if row >= rows - extra { base + 1 } else { base };
+        let w = 1.0 / cols as f32;
+        for col in 0..cols {
+            out.push(Rect {
+                x: col as f32 * w,
+                y: row as f32 * h,
+                w,
+                h,
+            });
+        }
+    }
+    out
+}
+
+// A user-defined layout, read from a JSON file in the layouts folder, e.g.:
+// { "name": "Big top", "players": { "3": [ {"x": 0, "y": 0, "w": 1, "h": 0.6}, ... ] } }
+// Player counts that aren't defined fall back to the default grid.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CustomLayout {
+    #[serde(skip)]
+    pub id: String,
+    pub name: String,
+    pub players: BTreeMap<usize, Vec<Rect>>,
+}
+
+pub fn layouts_dir() -> PathBuf {
+    PATH_PARTY.join("layouts")
+}
+
+pub fn scan_layouts() -> Vec<CustomLayout> {
+    let mut out: Vec<CustomLayout> = Vec::new();
+    let Ok(entries) = std::fs::read_dir(layouts_dir()) else {
+        return out;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        match load_layout_file(&path) {
+            Ok(layout) => out.push(layout),
+            Err(err) => println!("[partydeck] Invalid layout {}", err),
+        }
+    }
+    out.sort_by_key(|layout| layout.name.to_lowercase());
+    out
+}
+
+fn load_layout_file(path: &PathBuf) -> Result<CustomLayout, Box<dyn std::error::Error>> {
+    read_layout_file(path).map_err(|err| format!("{}: {}", path.display(), err).into())
+}
+
+fn read_layout_file(path: &PathBuf) -> Result<CustomLayout, Box<dyn std::error::Error>> {
+    let file = File::open(path)?;
+    let mut layout = serde_json::from_reader::<_, CustomLayout>(BufReader::new(file))?;
+    layout.id = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if layout.name.is_empty() {
+        layout.name = layout.id.clone();
+    }
+
+    for (count, rects) in &layout.players {
+        if rects.len() != *count {
+            return Err(format!(
+                "layout \"{}\": {} players defined with {} rectangles",
+                layout.name,
+                count,
+                rects.len()
+            )
+            .into());
+        }
+        for (i, rect) in rects.iter().enumerate() {
+            if !rect.is_valid() {
+                return Err(format!(
+                    "layout \"{}\": rectangle {} for {} players doesn't fit on the screen",
+                    layout.name,
+                    i + 1,
+                    count
+                )
+                .into());
+            }
+        }
+    }
+    Ok(layout)
+}
+
+// Loads the custom layout with the given ID, as selected in the settings. The default grid has
+// an empty ID and gives None, as does a layout that can't be loaded.
+pub fn load_layout(id: &str) -> Option<CustomLayout> {
+    if id.is_empty() {
+        return None;
+    }
+    let path = layouts_dir().join(format!("{id}.json"));
+    match load_layout_file(&path) {
+        Ok(layout) => Some(layout),
+        Err(err) => {
+            println!("[partydeck] Couldn't load layout {}", err);
+            None
+        }
+    }
+}
+
+// Returns the layout's areas for the given number of players on one monitor, falling back to the
+// default grid when there's no custom layout or it doesn't define that many players
+pub fn layout_for(
+    playercount: usize,
+    layout: Option<&CustomLayout>,
+    cfg: &PartyConfig,
+) -> Vec<Rect> {
+    if let Some(rects) = layout.and_then(|layout| layout.players.get(&playercount)) {
+        return rects.clone();
+    }
+    grid_layout(playercount, cfg.vertical_two_player)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> Rect {
+        Rect { x, y, w, h }
+    }
+
+    #[test]
+    fn grid_rects_are_valid() {
+        for playercount in 1..=8 {
+            assert!(grid_layout(playercount, false).iter().all(|r| r.is_valid()));
+        }
+    }
+
+    #[test]
+    fn rects_off_the_screen_are_invalid() {
+        assert!(rect(0.0, 0.0, 1.0, 1.0).is_valid());
+        assert!(rect(0.5, 0.0, 0.5, 1.0).is_valid());
+        assert!(!rect(0.6, 0.0, 0.5, 1.0).is_valid());
+        assert!(!rect(0.0, 0.5, 1.0, 0.6).is_valid());
+        assert!(!rect(-0.1, 0.0, 0.5, 0.5).is_valid());
+        assert!(!rect(0.0, 0.0, 0.0, 1.0).is_valid());
+        assert!(!rect(0.0, 0.0, 1.0, -0.5).is_valid());
+        assert!(!rect(0.0, 0.0, f32::NAN, 1.0).is_valid());
+        assert!(!rect(f32::NAN, 0.0, 1.0, 1.0).is_valid());
+    }
+}
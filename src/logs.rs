@@ -0,0 +1,207 @@
+use crate::paths::*;
+
+use std::error::Error;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::os::fd::OwnedFd;
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::mpsc::{Receiver, channel};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// Number of session log directories kept around; older ones are removed when a new session starts
+const SESSION_LOGS_KEPT: usize = 10;
+
+pub fn logs_dir() -> PathBuf {
+    PATH_PARTY.join("logs")
+}
+
+pub fn partydeck_log_path() -> PathBuf {
+    logs_dir().join("partydeck.log")
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().format("%H:%M:%S%.3f").to_string()
+}
+
+// Copies lines from a reader to the given outputs, prefixing each with a timestamp
+fn pipe_lines(reader: impl Read, outputs: Vec<Arc<Mutex<dyn Write + Send>>>) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let line = String::from_utf8_lossy(&buf);
+        let line = format!("[{}] {}\n", timestamp(), line.trim_end_matches(['\n', '\r']));
+        for output in &outputs {
+            let mut output = output.lock().unwrap();
+            let _ = output.write_all(line.as_bytes());
+            let _ = output.flush();
+        }
+    }
+}
+
+// PartyDeck's original stdout and stderr, while they're sent to the PartyDeck log
+pub struct PartydeckLog {
+    stdout: OwnedFd,
+    stderr: OwnedFd,
+    done: Receiver<()>,
+}
+
+impl PartydeckLog {
+    // Gives stdout and stderr back to the terminal, and waits briefly for the lines already
+    // written to reach the log, so they aren't lost when PartyDeck exits right after
+    pub fn finish(self) {
+        let _ = std::io::stdout().flush();
+        let _ = nix::unistd::dup2_stdout(&self.stdout);
+        let _ = nix::unistd::dup2_stderr(&self.stderr);
+        // Child processes still holding the pipe would keep it open, so don't wait on them forever
+        let _ = self.done.recv_timeout(Duration::from_secs(1));
+    }
+}
+
+// Sends PartyDeck's own stdout and stderr to the PartyDeck log as well as the terminal
+pub fn start_partydeck_log() -> Result<PartydeckLog, Box<dyn Error>> {
+    std::fs::create_dir_all(logs_dir())?;
+    let file = File::create(partydeck_log_path())?;
+
+    let stdout = nix::unistd::dup(std::io::stdout())?;
+    let stderr = nix::unistd::dup(std::io::stderr())?;
+    let terminal = File::from(nix::unistd::dup(std::io::stdout())?);
+    let (read, write) = nix::unistd::pipe()?;
+    nix::unistd::dup2_stdout(&write)?;
+    nix::unistd::dup2_stderr(&write)?;
+
+    let outputs: Vec<Arc<Mutex<dyn Write + Send>>> =
+        vec![Arc::new(Mutex::new(terminal)), Arc::new(Mutex::new(file))];
+    let (done_tx, done) = channel();
+    std::thread::spawn(move || {
+        pipe_lines(File::from(read), outputs);
+        let _ = done_tx.send(());
+    });
+
+    Ok(PartydeckLog {
+        stdout,
+        stderr,
+        done,
+    })
+}
+
+// Creates the log directory for a new session and removes the oldest ones
+pub fn new_session_log_dir() -> Result<PathBuf, Box<dyn Error>> {
+    let name = chrono::Local::now().format("%Y-%m-%d_%H-%M-%S").to_string();
+    let dir = logs_dir().join(name);
+    std::fs::create_dir_all(&dir)?;
+
+    let mut sessions = scan_session_logs();
+    while sessions.len() > SESSION_LOGS_KEPT {
+        let oldest = sessions.pop().unwrap();
+        if let Err(err) = std::fs::remove_dir_all(&oldest) {
+            println!("[partydeck] Failed to remove old logs {}: {}", oldest.display(), err);
+        }
+    }
+
+    Ok(dir)
+}
+
+// Writes the output of a child process spawned with piped stdout and stderr to a log file
+pub fn capture_output(child: &mut Child, log_path: &Path) -> Result<(), Box<dyn Error>> {
+    let file = OpenOptions::new().create(true).append(true).open(log_path)?;
+    let file: Arc<Mutex<dyn Write + Send>> = Arc::new(Mutex::new(file));
+
+    if let Some(stdout) = child.stdout.take() {
+        let outputs = vec![file.clone()];
+        std::thread::spawn(move || pipe_lines(stdout, outputs));
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let outputs = vec![file.clone()];
+        std::thread::spawn(move || pipe_lines(stderr, outputs));
+    }
+
+    Ok(())
+}
+
+// Returns session log directories, newest first
+pub fn scan_session_logs() -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    let Ok(entries) = std::fs::read_dir(logs_dir()) else {
+        return out;
+    };
+    for entry in entries.flatten() {
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            out.push(entry.path());
+        }
+    }
+    out.sort();
+    out.reverse();
+    out
+}
+
+// Returns every log file that can be viewed: PartyDeck's own log, then each session's instance logs
+pub fn scan_log_files() -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    if partydeck_log_path().exists() {
+        out.push(partydeck_log_path());
+    }
+    for session in scan_session_logs() {
+        let Ok(entries) = std::fs::read_dir(&session) else {
+            continue;
+        };
+        let mut files: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+            .collect();
+        files.sort();
+        out.extend(files);
+    }
+    out
+}
+
+// Most bytes read from the end of a log file when showing its tail
+const LOG_TAIL_BYTES: u64 = 256 * 1024;
+
+// Reads the last lines of a log file. Only the end of the file is read, as this runs every second
+// while the log is shown, and game logs can grow large.
+pub fn read_log_tail(path: &Path, lines: usize) -> String {
+    let Ok(mut file) = File::open(path) else {
+        return String::new();
+    };
+    let len = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    let start = len.saturating_sub(LOG_TAIL_BYTES);
+    let mut bytes: Vec<u8> = Vec::new();
+    if file.seek(SeekFrom::Start(start)).is_err() || file.read_to_end(&mut bytes).is_err() {
+        return String::new();
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let mut all: Vec<&str> = text.lines().collect();
+    // The first line is most likely cut off
+    if start > 0 && !all.is_empty() {
+        all.remove(0);
+    }
+    all[all.len().saturating_sub(lines)..].join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tail_of_a_large_log() {
+        let path = std::env::temp_dir().join(format!("partydeck-test-{}.log", std::process::id()));
+        let text: Vec<String> = (0..100_000).map(|i| format!("line {i}")).collect();
+        std::fs::write(&path, text.join("\n")).unwrap();
+
+        assert_eq!(read_log_tail(&path, 2), "line 99998\nline 99999");
+        // Only whole lines are returned, even when the tail is shorter than asked for
+        let tail = read_log_tail(&path, 100_000);
+        assert!(tail.lines().count() < 100_000);
+        assert!(text.ends_with(&tail.lines().map(str::to_string).collect::<Vec<_>>()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
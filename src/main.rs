@@ -1,12 +1,23 @@
 mod app;
+mod cli;
 mod handler;
 mod input;
 mod instance;
+mod keymap;
 mod launch;
+mod layout;
+mod logs;
 mod monitor;
 mod paths;
+mod placement;
+mod plan;
+mod presets;
 mod profiles;
+mod remap;
+mod session;
+mod snapshots;
 mod util;
+mod virtual_pads;
 
 use crate::app::*;
 use crate::handler::Handler;
@@ -101,6 +112,8 @@ fn main() -> eframe::Result {
         .expect("Failed to create handlers directory");
     std::fs::create_dir_all(PATH_PARTY.join("profiles"))
         .expect("Failed to create profiles directory");
+    std::fs::create_dir_all(PATH_PARTY.join("layouts"))
+        .expect("Failed to create layouts directory");
     if !PATH_PARTY.join("goldberg_data").exists() {
         std::fs::create_dir_all(PATH_PARTY.join("goldberg_data/steam_settings"))
             .expect("Failed to create goldberg data!");
@@ -111,6 +124,22 @@ fn main() -> eframe::Result {
     remove_guest_profiles().unwrap();
     clear_tmp().unwrap();
 
+    if args.get(1).is_some_and(|arg| arg == "launch") {
+        // A dry run prints its plan to stdout, which has to stay free of log timestamps
+        let log = match args.iter().any(|arg| arg == "--dry-run") {
+            true => None,
+            false => start_partydeck_log(),
+        };
+        let result = cli::launch_from_cli(&args[2..], &monitors);
+        if let Err(e) = &result {
+            eprintln!("[partydeck] Launch failed: {}", e);
+        }
+        if let Some(log) = log {
+            log.finish();
+        }
+        std::process::exit(if result.is_ok() { 0 } else { 1 });
+    }
+
     let scrheight = monitors[0].height();
 
     let scale = match fullscreen {
@@ -130,9 +159,11 @@ fn main() -> eframe::Result {
         ..Default::default()
     };
 
+    let log = start_partydeck_log();
+
     println!("[partydeck] Starting eframe app...");
 
-    eframe::run_native(
+    let result = eframe::run_native(
         "PartyDeck",
         options,
         Box::new(|cc| {
@@ -144,16 +175,43 @@ fn main() -> eframe::Result {
                 handler_lite,
             )))
         }),
-    )
+    );
+
+    if let Some(log) = log {
+        log.finish();
+    }
+    result
+}
+
+// Starts the PartyDeck log, carrying on without it if that fails
+fn start_partydeck_log() -> Option<logs::PartydeckLog> {
+    match logs::start_partydeck_log() {
+        Ok(log) => Some(log),
+        Err(err) => {
+            println!("[partydeck] Failed to start PartyDeck log: {}", err);
+            None
+        }
+    }
 }
 
 static USAGE_TEXT: &str = r#"
 {}
 Usage: partydeck [OPTIONS]
+       partydeck launch --handler <handler> --player <spec> [--player <spec>...] [--dry-run [--output <file>]]
 
 Options:
     --exec <executable>   Execute the specified executable in splitscreen. If this isn't specified, PartyDeck will launch in the regular GUI mode.
     --args [args]         Specify arguments for the executable to be launched with. Must be quoted if containing spaces.
     --fullscreen          Start the GUI in fullscreen mode
     --kwin                Launch PartyDeck inside of a KWin session
+
+Launch options:
+    --handler <handler>   Name of the handler's folder in the PartyDeck data folder's handlers directory
+    --player <spec>       Add a player. The spec is a comma-separated list of:
+                            profile=<name>   Profile to use; leave out or use "Guest" for a guest profile
+                            device=<id>      Device ID as shown when hovering the device in the GUI, or an input
+                                             device path, e.g. /dev/input/by-id/...; can be repeated
+                            monitor=<index>  Monitor to place the instance on (default: 0)
+    --dry-run             Print the launch plan as JSON instead of launching anything
+    --output <file>       Write the dry run's launch plan to a file instead of stdout
 "#;
@@ -1,15 +1,33 @@
 #[derive(Clone)]
 pub struct Monitor {
     name: String,
+    x: i32,
+    y: i32,
     width: u32,
     height: u32,
 }
 
 impl Monitor {
+    #[cfg(test)]
+    pub fn new(name: &str, (x, y): (i32, i32), width: u32, height: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    // Position in the desktop's global coordinates
+    pub fn position(&self) -> (i32, i32) {
+        (self.x, self.y)
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -25,10 +43,13 @@ pub fn get_monitors_sdl() -> Vec<Monitor> {
     let count = video.num_video_displays().unwrap();
     let mut monitors = Vec::new();
     for i in 0..count {
+        let bounds = video.display_bounds(i).unwrap();
         monitors.push(Monitor {
             name: video.display_name(i).unwrap(),
-            width: video.display_bounds(i).unwrap().width(),
-            height: video.display_bounds(i).unwrap().height(),
+            x: bounds.x(),
+            y: bounds.y(),
+            width: bounds.width(),
+            height: bounds.height(),
         });
     }
     monitors
@@ -0,0 +1,271 @@
+use super::*;
+
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+
+pub fn connect(monitors: &[Monitor]) -> Result<Box<dyn WindowPoller>, Box<dyn Error>> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE")
+        .map_err(|_| "HYPRLAND_INSTANCE_SIGNATURE is not set; is Hyprland running?")?;
+
+    // Hyprland moved its sockets from /tmp to the runtime dir in 0.40
+    let mut socket = PathBuf::from("/tmp/hypr")
+        .join(&signature)
+        .join(".socket.sock");
+    if let Ok(runtime_dir) = std::env::var("XDG_RUNTIME_DIR") {
+        let path = PathBuf::from(runtime_dir)
+            .join("hypr")
+            .join(&signature)
+            .join(".socket.sock");
+        if path.exists() {
+            socket = path;
+        }
+    }
+    if !socket.exists() {
+        return Err(format!("Hyprland socket {} not found", socket.display()).into());
+    }
+
+    Ok(Box::new(HyprlandPoller {
+        socket,
+        monitors: monitors.to_vec(),
+        placed: HashSet::new(),
+    }))
+}
+
+// Floats gamescope windows and moves them into place through Hyprland's IPC socket
+struct HyprlandPoller {
+    socket: PathBuf,
+    monitors: Vec<Monitor>,
+    // Addresses of the windows that were floated, along with the PID they were placed for
+    placed: HashSet<(String, u32)>,
+}
+
+impl HyprlandPoller {
+    // Hyprland takes one request per connection, like hyprctl
+    fn request(&self, cmd: &str) -> Result<String, Box<dyn Error>> {
+        let mut stream = UnixStream::connect(&self.socket)?;
+        stream.write_all(cmd.as_bytes())?;
+        let mut reply = String::new();
+        stream.read_to_string(&mut reply)?;
+        Ok(reply)
+    }
+
+    fn dispatch(&self, dispatches: &[String]) -> Result<(), Box<dyn Error>> {
+        let batch: Vec<String> = dispatches.iter().map(|d| format!("dispatch {d}")).collect();
+        let reply = self.request(&format!("[[BATCH]]{}", batch.join(";")))?;
+        for (line, dispatch) in reply.split("\n\n").zip(dispatches) {
+            if line.trim() != "ok" {
+                return Err(format!("\"{}\" failed: {}", dispatch, line.trim()).into());
+            }
+        }
+        Ok(())
+    }
+
+    // (address, PID) of every window
+    fn clients(&self) -> Result<Vec<(String, u32)>, Box<dyn Error>> {
+        let clients: Value = serde_json::from_str(&self.request("j/clients")?)?;
+        Ok(clients
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|client| {
+                let address = client["address"].as_str()?.to_string();
+                let pid = client["pid"].as_i64().filter(|pid| *pid > 0)?;
+                Some((address, pid as u32))
+            })
+            .collect())
+    }
+
+    // Monitors in logical (scaled) coordinates, ordered by ID
+    fn outputs(&self) -> Result<Vec<Output>, Box<dyn Error>> {
+        let monitors: Value = serde_json::from_str(&self.request("j/monitors")?)?;
+        let mut monitors: Vec<&Value> = monitors.as_array().into_iter().flatten().collect();
+        monitors.sort_by_key(|m| m["id"].as_i64().unwrap_or(0));
+        Ok(monitors
+            .iter()
+            .map(|m| {
+                let scale = m["scale"].as_f64().filter(|s| *s > 0.0).unwrap_or(1.0);
+                let mut width = m["width"].as_f64().unwrap_or(0.0) / scale;
+                let mut height = m["height"].as_f64().unwrap_or(0.0) / scale;
+                // Rotated by 90 or 270 degrees
+                if m["transform"].as_i64().is_some_and(|t| t % 2 == 1) {
+                    std::mem::swap(&mut width, &mut height);
+                }
+                (
+                    m["name"].as_str().unwrap_or_default().to_string(),
+                    (
+                        m["x"].as_i64().unwrap_or(0) as i32,
+                        m["y"].as_i64().unwrap_or(0) as i32,
+                        width.round() as u32,
+                        height.round() as u32,
+                    ),
+                )
+            })
+            .collect())
+    }
+}
+
+impl WindowPoller for HyprlandPoller {
+    fn poll(&mut self, targets: &[WindowPlacement]) -> Result<(), Box<dyn Error>> {
+        let pending: Vec<(String, u32)> = self
+            .clients()?
+            .into_iter()
+            .filter(|client| !self.placed.contains(client))
+            .filter(|(_, pid)| targets.iter().any(|t| t.pid == *pid))
+            .collect();
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let outputs = self.outputs()?;
+        for (address, pid) in pending {
+            let Some(target) = targets.iter().find(|t| t.pid == pid) else {
+                continue;
+            };
+            let Some((mx, my, mw, mh)) = target_output(&outputs, &self.monitors, target.monitor)
+            else {
+                continue;
+            };
+
+            let (x, y, w, h) = target.rect.to_pixels(mw, mh);
+            let window = format!("address:{address}");
+            self.dispatch(&[
+                format!("setfloating {window}"),
+                format!("resizewindowpixel exact {w} {h},{window}"),
+                format!("movewindowpixel exact {} {},{window}", mx + x, my + y),
+            ])?;
+            self.placed.insert((address, pid));
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self) -> Result<(), Box<dyn Error>> {
+        let dispatches: Vec<String> = self
+            .clients()?
+            .into_iter()
+            .filter(|client| self.placed.contains(client))
+            .map(|(address, _)| format!("settiled address:{address}"))
+            .collect();
+        if !dispatches.is_empty() {
+            self.dispatch(&dispatches)?;
+        }
+        self.placed.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Rect;
+    use serde_json::json;
+    use std::os::unix::net::UnixListener;
+    use std::sync::{Arc, Mutex};
+
+    // Answers socket requests like Hyprland would, one per connection, recording the dispatches
+    fn fake_hyprland(listener: UnixListener, dispatches: Arc<Mutex<Vec<String>>>) {
+        let clients = json!([
+            { "address": "0x10", "pid": 100 },
+            { "address": "0x11", "pid": 300 },
+            { "address": "0x12", "pid": 200 },
+            { "address": "0x13", "pid": -1 },
+        ]);
+        // Not in SDL's order; the second one in SDL's order is scaled and rotated
+        let monitors = json!([
+            { "id": 1, "name": "DP-1", "x": 0, "y": 0, "width": 1920, "height": 1080, "scale": 1.0, "transform": 0 },
+            { "id": 0, "name": "DP-2", "x": 1920, "y": 0, "width": 2560, "height": 1440, "scale": 2.0, "transform": 1 },
+        ]);
+
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut buf = [0u8; 4096];
+            let len = stream.read(&mut buf).unwrap();
+            let request = String::from_utf8_lossy(&buf[..len]).to_string();
+
+            let reply = match request.as_str() {
+                "quit" => return,
+                "j/clients" => clients.to_string(),
+                "j/monitors" => monitors.to_string(),
+                _ => {
+                    let batch = request.strip_prefix("[[BATCH]]").unwrap();
+                    let mut dispatches = dispatches.lock().unwrap();
+                    let mut replies = Vec::new();
+                    for dispatch in batch.split(';') {
+                        dispatches.push(dispatch.to_string());
+                        replies.push("ok");
+                    }
+                    replies.join("\n\n")
+                }
+            };
+            stream.write_all(reply.as_bytes()).unwrap();
+        }
+    }
+
+    #[test]
+    fn floats_and_places_target_windows() {
+        let dir = std::env::temp_dir().join(format!("partydeck-test-hypr-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let socket = dir.join(".socket.sock");
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let dispatches = Arc::new(Mutex::new(Vec::new()));
+        let server = std::thread::spawn({
+            let dispatches = dispatches.clone();
+            move || fake_hyprland(listener, dispatches)
+        });
+
+        let targets = [
+            WindowPlacement {
+                pid: 100,
+                monitor: 0,
+                rect: Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 1.0,
+                    h: 0.5,
+                },
+            },
+            WindowPlacement {
+                pid: 200,
+                monitor: 1,
+                rect: Rect {
+                    x: 0.0,
+                    y: 0.5,
+                    w: 1.0,
+                    h: 0.5,
+                },
+            },
+        ];
+        let mut poller = HyprlandPoller {
+            socket: socket.clone(),
+            monitors: vec![
+                Monitor::new("DP-1", (0, 0), 1920, 1080),
+                Monitor::new("DP-2", (1920, 0), 1280, 720),
+            ],
+            placed: HashSet::new(),
+        };
+        poller.poll(&targets).unwrap();
+        // Windows that were already placed are left alone
+        poller.poll(&targets).unwrap();
+        poller.restore().unwrap();
+        poller.request("quit").unwrap();
+        server.join().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            *dispatches.lock().unwrap(),
+            [
+                "dispatch setfloating address:0x10",
+                "dispatch resizewindowpixel exact 1920 540,address:0x10",
+                "dispatch movewindowpixel exact 0 0,address:0x10",
+                "dispatch setfloating address:0x12",
+                "dispatch resizewindowpixel exact 720 640,address:0x12",
+                "dispatch movewindowpixel exact 1920 640,address:0x12",
+                "dispatch settiled address:0x10",
+                "dispatch settiled address:0x12",
+            ]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+use crate::paths::PATH_PARTY;
+use crate::util::{kwin_dbus_start_script, kwin_dbus_unload_script};
+
+pub struct KWinPlacer {
+    monitors: Vec<Monitor>,
+    loaded: bool,
+}
+
+impl KWinPlacer {
+    pub fn new(monitors: &[Monitor]) -> Self {
+        Self {
+            monitors: monitors.to_vec(),
+            loaded: false,
+        }
+    }
+}
+
+impl WindowPlacer for KWinPlacer {
+    fn name(&self) -> &str {
+        "KWin"
+    }
+
+    fn place(&mut self, windows: &[WindowPlacement]) -> Result<(), Box<dyn Error>> {
+        let dir = PATH_PARTY.join("tmp");
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join("splitscreen_kwin.js");
+        std::fs::write(&path, kwin_script(windows, &self.monitors))?;
+
+        // KWin keeps running the old script until it's unloaded, so reload it with the new windows
+        if self.loaded {
+            kwin_dbus_unload_script()?;
+            self.loaded = false;
+        }
+        kwin_dbus_start_script(path)?;
+        self.loaded = true;
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.loaded {
+            kwin_dbus_unload_script()?;
+            self.loaded = false;
+        }
+        Ok(())
+    }
+}
+
+// Fills the KWin splitscreen script template with the geometry of each window, by gamescope PID.
+// Geometry is relative to the window's target monitor, given by its position so the script can
+// find the matching KWin output; windows that opened on another output are moved over.
+pub fn kwin_script(windows: &[WindowPlacement], monitors: &[Monitor]) -> String {
+    let entries: Vec<String> = windows
+        .iter()
+        .map(|w| {
+            let output = match monitors.get(w.monitor) {
+                Some(monitor) => {
+                    let (x, y) = monitor.position();
+                    format!("{{ x: {x}, y: {y} }}")
+                }
+                None => "null".to_string(),
+            };
+            format!(
+                "  {}: {{ output: {}, x: {}, y: {}, width: {}, height: {} }}",
+                w.pid, output, w.rect.x, w.rect.y, w.rect.w, w.rect.h
+            )
+        })
+        .collect();
+    let table = format!("const windows = {{\n{}\n}};", entries.join(",\n"));
+
+    include_str!("../../res/splitscreen_kwin.js").replace("// %WINDOWS%", &table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::grid_layout;
+
+    fn monitors() -> Vec<Monitor> {
+        vec![
+            Monitor::new("DP-1", (0, 0), 1920, 1080),
+            Monitor::new("HDMI-A-1", (1920, 0), 2560, 1440),
+        ]
+    }
+
+    fn placements(rects: Vec<Rect>, monitor: usize) -> Vec<WindowPlacement> {
+        rects
+            .into_iter()
+            .enumerate()
+            .map(|(i, rect)| WindowPlacement {
+                pid: 1000 + i as u32,
+                monitor,
+                rect,
+            })
+            .collect()
+    }
+
+    fn windows_table(script: &str) -> &str {
+        let start = script.find("const windows = ").unwrap();
+        let end = start + script[start..].find("};").unwrap();
+        &script[start..end + 2]
+    }
+
+    #[test]
+    fn two_players() {
+        let script = kwin_script(&placements(grid_layout(2, false), 0), &monitors());
+        assert!(!script.contains("// %WINDOWS%"));
+        assert_eq!(
+            windows_table(&script),
+            "const windows = {\n\
+             \x20 1000: { output: { x: 0, y: 0 }, x: 0, y: 0, width: 1, height: 0.5 },\n\
+             \x20 1001: { output: { x: 0, y: 0 }, x: 0, y: 0.5, width: 1, height: 0.5 }\n\
+             };"
+        );
+
+        let script = kwin_script(&placements(grid_layout(2, true), 1), &monitors());
+        assert_eq!(
+            windows_table(&script),
+            "const windows = {\n\
+             \x20 1000: { output: { x: 1920, y: 0 }, x: 0, y: 0, width: 0.5, height: 1 },\n\
+             \x20 1001: { output: { x: 1920, y: 0 }, x: 0.5, y: 0, width: 0.5, height: 1 }\n\
+             };"
+        );
+    }
+
+    #[test]
+    fn four_players() {
+        let script = kwin_script(&placements(grid_layout(4, false), 1), &monitors());
+        assert_eq!(
+            windows_table(&script),
+            "const windows = {\n\
+             \x20 1000: { output: { x: 1920, y: 0 }, x: 0, y: 0, width: 0.5, height: 0.5 },\n\
+             \x20 1001: { output: { x: 1920, y: 0 }, x: 0.5, y: 0, width: 0.5, height: 0.5 },\n\
+             \x20 1002: { output: { x: 1920, y: 0 }, x: 0, y: 0.5, width: 0.5, height: 0.5 },\n\
+             \x20 1003: { output: { x: 1920, y: 0 }, x: 0.5, y: 0.5, width: 0.5, height: 0.5 }\n\
+             };"
+        );
+        // The rest of the template is left as it is
+        assert!(script.contains("function gamescopeSplitscreen()"));
+    }
+
+    #[test]
+    fn unknown_monitor_keeps_the_current_output() {
+        let script = kwin_script(&placements(vec![Rect::FULL], 2), &monitors());
+        assert_eq!(
+            windows_table(&script),
+            "const windows = {\n\
+             \x20 1000: { output: null, x: 0, y: 0, width: 1, height: 1 }\n\
+             };"
+        );
+    }
+}
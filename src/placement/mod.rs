@@ -0,0 +1,190 @@
+mod hyprland;
+mod kwin;
+mod sway;
+mod x11;
+
+use crate::app::{PartyConfig, PlacementBackend};
+use crate::layout::Rect;
+use crate::monitor::Monitor;
+
+use std::error::Error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+
+// Where a game instance's window should go: its gamescope process, and its area on its monitor
+#[derive(Clone)]
+pub struct WindowPlacement {
+    pub pid: u32,
+    pub monitor: usize,
+    pub rect: Rect,
+}
+
+// A monitor's position and size in pixels: x, y, width, height
+pub type MonitorGeometry = (i32, i32, u32, u32);
+
+// An output as a compositor reports it: its name and geometry
+pub type Output = (String, MonitorGeometry);
+
+// Finds the output a plan's monitor index refers to. The index follows SDL's display order, which
+// compositors don't share, so the SDL monitor is looked up by name and then by position; the
+// compositor's own order is only a last resort.
+pub fn target_output(
+    outputs: &[Output],
+    monitors: &[Monitor],
+    index: usize,
+) -> Option<MonitorGeometry> {
+    if let Some(monitor) = monitors.get(index)
+        && let Some((_, geometry)) = outputs
+            .iter()
+            .find(|(name, _)| name == monitor.name())
+            .or_else(|| {
+                outputs
+                    .iter()
+                    .find(|(_, (x, y, _, _))| (*x, *y) == monitor.position())
+            })
+    {
+        return Some(*geometry);
+    }
+    outputs.get(index).map(|(_, geometry)| *geometry)
+}
+
+// Moves and resizes the gamescope windows of a session according to the layout
+pub trait WindowPlacer: Send {
+    fn name(&self) -> &str;
+    // Places the given windows, replacing any previous placement; called again whenever an instance restarts
+    fn place(&mut self, windows: &[WindowPlacement]) -> Result<(), Box<dyn Error>>;
+    // Undoes the placement once the session has ended
+    fn stop(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+// A placement backend that has to keep looking for new windows by itself
+pub trait WindowPoller: Send {
+    // Places the target windows that haven't been placed yet
+    fn poll(&mut self, targets: &[WindowPlacement]) -> Result<(), Box<dyn Error>>;
+    // Puts back whatever was changed on windows that are still open when the session ends
+    fn restore(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+// Connects a poller, given the monitors the plan's monitor indices refer to
+type PollerConnect = fn(&[Monitor]) -> Result<Box<dyn WindowPoller>, Box<dyn Error>>;
+
+// Runs a WindowPoller a few times a second on its own thread until the session ends
+pub struct PollingPlacer {
+    name: &'static str,
+    connect: PollerConnect,
+    monitors: Vec<Monitor>,
+    windows: Arc<Mutex<Vec<WindowPlacement>>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PollingPlacer {
+    pub fn new(name: &'static str, connect: PollerConnect, monitors: &[Monitor]) -> Self {
+        Self {
+            name,
+            connect,
+            monitors: monitors.to_vec(),
+            windows: Arc::new(Mutex::new(Vec::new())),
+            stop: Arc::new(AtomicBool::new(false)),
+            thread: None,
+        }
+    }
+}
+
+impl WindowPlacer for PollingPlacer {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn place(&mut self, windows: &[WindowPlacement]) -> Result<(), Box<dyn Error>> {
+        *self.windows.lock().unwrap() = windows.to_vec();
+        if self.thread.is_some() {
+            return Ok(());
+        }
+
+        let mut poller = (self.connect)(&self.monitors)?;
+        let name = self.name;
+        let windows = self.windows.clone();
+        let stop = self.stop.clone();
+        self.thread = Some(std::thread::spawn(move || {
+            while !stop.load(Ordering::Relaxed) {
+                let targets = windows.lock().unwrap().clone();
+                if let Err(err) = poller.poll(&targets) {
+                    println!("[partydeck] {} window placement error: {}", name, err);
+                }
+                std::thread::sleep(std::time::Duration::from_millis(250));
+            }
+            if let Err(err) = poller.restore() {
+                println!("[partydeck] Failed to restore {} windows: {}", name, err);
+            }
+        }));
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Box<dyn Error>> {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        Ok(())
+    }
+}
+
+pub fn placement_backend_name(cfg: &PartyConfig) -> Option<&'static str> {
+    if !cfg.window_placement {
+        return None;
+    }
+    Some(match cfg.placement_backend {
+        PlacementBackend::KWin => "kwin",
+        PlacementBackend::X11 => "x11",
+        PlacementBackend::Sway => "sway",
+        PlacementBackend::Hyprland => "hyprland",
+    })
+}
+
+// monitors are the ones the plan's monitor indices refer to, in SDL's order
+pub fn new_placer(cfg: &PartyConfig, monitors: &[Monitor]) -> Option<Box<dyn WindowPlacer>> {
+    if !cfg.window_placement {
+        return None;
+    }
+    Some(match cfg.placement_backend {
+        PlacementBackend::KWin => Box::new(kwin::KWinPlacer::new(monitors)),
+        PlacementBackend::X11 => Box::new(PollingPlacer::new("X11", x11::connect, monitors)),
+        PlacementBackend::Sway => Box::new(PollingPlacer::new("Sway", sway::connect, monitors)),
+        PlacementBackend::Hyprland => {
+            Box::new(PollingPlacer::new("Hyprland", hyprland::connect, monitors))
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outputs_follow_sdl_order() {
+        let outputs: Vec<Output> = vec![
+            ("DP-2".to_string(), (1920, 0, 2560, 1440)),
+            ("DP-1".to_string(), (0, 0, 1920, 1080)),
+        ];
+        let monitors = [
+            Monitor::new("DP-1", (0, 0), 1920, 1080),
+            Monitor::new("DELL U2415", (1920, 0), 2560, 1440),
+        ];
+        // By name, then by position
+        assert_eq!(
+            target_output(&outputs, &monitors, 0),
+            Some((0, 0, 1920, 1080))
+        );
+        assert_eq!(
+            target_output(&outputs, &monitors, 1),
+            Some((1920, 0, 2560, 1440))
+        );
+        // Falls back to the compositor's order
+        assert_eq!(target_output(&outputs, &[], 1), Some((0, 0, 1920, 1080)));
+        assert_eq!(target_output(&outputs, &monitors, 2), None);
+    }
+}
@@ -0,0 +1,304 @@
+use super::*;
+
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+
+// i3/Sway IPC message types
+const RUN_COMMAND: u32 = 0;
+const GET_OUTPUTS: u32 = 3;
+const GET_TREE: u32 = 4;
+
+const IPC_MAGIC: &[u8; 6] = b"i3-ipc";
+
+pub fn connect(monitors: &[Monitor]) -> Result<Box<dyn WindowPoller>, Box<dyn Error>> {
+    let path = std::env::var("SWAYSOCK").map_err(|_| "SWAYSOCK is not set; is Sway running?")?;
+    Ok(Box::new(SwayPoller {
+        stream: UnixStream::connect(path)?,
+        monitors: monitors.to_vec(),
+        placed: HashMap::new(),
+    }))
+}
+
+// Floats gamescope windows and moves them into place through Sway's IPC socket
+struct SwayPoller {
+    stream: UnixStream,
+    monitors: Vec<Monitor>,
+    // Windows that were placed, by container ID and the PID they were placed for, along with
+    // how they were before so that can be put back
+    placed: HashMap<(i64, u32), SwayWindow>,
+}
+
+// A window in Sway's tree
+#[derive(Clone)]
+struct SwayWindow {
+    id: i64,
+    pid: u32,
+    floating: bool,
+    // Border style (none, normal, pixel or csd) and width in pixels
+    border: String,
+    border_width: i64,
+}
+
+impl SwayWindow {
+    // The command that gives the window its border back
+    fn border_cmd(&self) -> String {
+        match self.border.as_str() {
+            "normal" | "pixel" => format!("border {} {}", self.border, self.border_width),
+            "none" | "csd" => format!("border {}", self.border),
+            _ => "border normal".to_string(),
+        }
+    }
+}
+
+impl SwayPoller {
+    fn request(&mut self, kind: u32, payload: &str) -> Result<Value, Box<dyn Error>> {
+        let mut msg = Vec::with_capacity(14 + payload.len());
+        msg.extend_from_slice(IPC_MAGIC);
+        msg.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+        msg.extend_from_slice(&kind.to_ne_bytes());
+        msg.extend_from_slice(payload.as_bytes());
+        self.stream.write_all(&msg)?;
+
+        let mut header = [0u8; 14];
+        self.stream.read_exact(&mut header)?;
+        if &header[..6] != IPC_MAGIC {
+            return Err("Invalid reply from Sway".into());
+        }
+        let len = u32::from_ne_bytes(header[6..10].try_into()?) as usize;
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body)?;
+        Ok(serde_json::from_slice(&body)?)
+    }
+
+    fn command(&mut self, cmd: &str) -> Result<(), Box<dyn Error>> {
+        let reply = self.request(RUN_COMMAND, cmd)?;
+        // Every command in the list gets its own result
+        for result in reply.as_array().into_iter().flatten() {
+            if result["success"] != Value::Bool(true) {
+                return Err(format!("\"{}\" failed: {}", cmd, result["error"]).into());
+            }
+        }
+        Ok(())
+    }
+
+    fn windows(&mut self) -> Result<Vec<SwayWindow>, Box<dyn Error>> {
+        let mut windows = Vec::new();
+        tree_windows(&self.request(GET_TREE, "")?, &mut windows);
+        Ok(windows)
+    }
+
+    // Active outputs in logical (scaled) coordinates, in the order Sway reports them
+    fn outputs(&mut self) -> Result<Vec<Output>, Box<dyn Error>> {
+        let outputs = self.request(GET_OUTPUTS, "")?;
+        Ok(outputs
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|output| output["active"] == Value::Bool(true))
+            .map(|output| {
+                let rect = &output["rect"];
+                (
+                    output["name"].as_str().unwrap_or_default().to_string(),
+                    (
+                        rect["x"].as_i64().unwrap_or(0) as i32,
+                        rect["y"].as_i64().unwrap_or(0) as i32,
+                        rect["width"].as_u64().unwrap_or(0) as u32,
+                        rect["height"].as_u64().unwrap_or(0) as u32,
+                    ),
+                )
+            })
+            .collect())
+    }
+}
+
+// Collects every window in the tree
+fn tree_windows(node: &Value, out: &mut Vec<SwayWindow>) {
+    if let Some(id) = node["id"].as_i64()
+        && let Some(pid) = node["pid"].as_u64()
+    {
+        out.push(SwayWindow {
+            id,
+            pid: pid as u32,
+            floating: node["type"] == "floating_con",
+            border: node["border"].as_str().unwrap_or_default().to_string(),
+            border_width: node["current_border_width"].as_i64().unwrap_or(0),
+        });
+    }
+    for key in ["nodes", "floating_nodes"] {
+        for child in node[key].as_array().into_iter().flatten() {
+            tree_windows(child, out);
+        }
+    }
+}
+
+impl WindowPoller for SwayPoller {
+    fn poll(&mut self, targets: &[WindowPlacement]) -> Result<(), Box<dyn Error>> {
+        let windows = self.windows()?;
+        if windows.iter().all(|w| {
+            self.placed.contains_key(&(w.id, w.pid)) || !targets.iter().any(|t| t.pid == w.pid)
+        }) {
+            return Ok(());
+        }
+
+        let outputs = self.outputs()?;
+        for window in windows {
+            let Some(target) = targets.iter().find(|t| t.pid == window.pid) else {
+                continue;
+            };
+            if self.placed.contains_key(&(window.id, window.pid)) {
+                continue;
+            }
+            let Some((mx, my, mw, mh)) = target_output(&outputs, &self.monitors, target.monitor)
+            else {
+                continue;
+            };
+
+            let (x, y, w, h) = target.rect.to_pixels(mw, mh);
+            self.command(&format!(
+                "[con_id={}] floating enable, border none, resize set width {w} px height {h} px, move absolute position {} px {} px",
+                window.id,
+                mx + x,
+                my + y
+            ))?;
+            self.placed.insert((window.id, window.pid), window);
+        }
+        Ok(())
+    }
+
+    fn restore(&mut self) -> Result<(), Box<dyn Error>> {
+        for window in self.windows()? {
+            let Some(original) = self.placed.get(&(window.id, window.pid)).cloned() else {
+                continue;
+            };
+            let cmd = match original.floating {
+                true => format!("[con_id={}] {}", window.id, original.border_cmd()),
+                false => format!(
+                    "[con_id={}] floating disable, {}",
+                    window.id,
+                    original.border_cmd()
+                ),
+            };
+            self.command(&cmd)?;
+        }
+        self.placed.clear();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Rect;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    // Answers i3 IPC requests like Sway would, recording the commands it's sent
+    fn fake_sway(mut stream: UnixStream, commands: Arc<Mutex<Vec<String>>>) {
+        let tree = json!({
+            "id": 1,
+            "nodes": [{
+                "id": 2,
+                "nodes": [
+                    { "id": 10, "pid": 100, "type": "con", "border": "normal", "current_border_width": 2, "nodes": [] },
+                    { "id": 11, "pid": 300, "type": "con", "border": "normal", "current_border_width": 2, "nodes": [] },
+                ],
+                "floating_nodes": [
+                    { "id": 12, "pid": 200, "type": "floating_con", "border": "pixel", "current_border_width": 3, "nodes": [] },
+                ],
+            }],
+        });
+        // Not in SDL's order
+        let outputs = json!([
+            { "name": "DP-2", "active": true, "rect": { "x": 1920, "y": 0, "width": 1280, "height": 720 } },
+            { "name": "HDMI-A-1", "active": false, "rect": { "x": 0, "y": 0, "width": 0, "height": 0 } },
+            { "name": "DP-1", "active": true, "rect": { "x": 0, "y": 0, "width": 1920, "height": 1080 } },
+        ]);
+
+        let mut header = [0u8; 14];
+        while stream.read_exact(&mut header).is_ok() {
+            assert_eq!(&header[..6], IPC_MAGIC);
+            let len = u32::from_ne_bytes(header[6..10].try_into().unwrap()) as usize;
+            let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+            let mut payload = vec![0u8; len];
+            stream.read_exact(&mut payload).unwrap();
+
+            let reply = match kind {
+                RUN_COMMAND => {
+                    commands
+                        .lock()
+                        .unwrap()
+                        .push(String::from_utf8(payload).unwrap());
+                    json!([{ "success": true }])
+                }
+                GET_OUTPUTS => outputs.clone(),
+                GET_TREE => tree.clone(),
+                _ => panic!("Unexpected IPC message type {kind}"),
+            };
+            let body = reply.to_string();
+            let mut msg = IPC_MAGIC.to_vec();
+            msg.extend_from_slice(&(body.len() as u32).to_ne_bytes());
+            msg.extend_from_slice(&kind.to_ne_bytes());
+            msg.extend_from_slice(body.as_bytes());
+            stream.write_all(&msg).unwrap();
+        }
+    }
+
+    #[test]
+    fn floats_and_places_target_windows() {
+        let (client, server) = UnixStream::pair().unwrap();
+        let commands = Arc::new(Mutex::new(Vec::new()));
+        let server = std::thread::spawn({
+            let commands = commands.clone();
+            move || fake_sway(server, commands)
+        });
+
+        let targets = [
+            WindowPlacement {
+                pid: 100,
+                monitor: 0,
+                rect: Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 1.0,
+                    h: 0.5,
+                },
+            },
+            WindowPlacement {
+                pid: 200,
+                monitor: 1,
+                rect: Rect {
+                    x: 0.5,
+                    y: 0.5,
+                    w: 0.5,
+                    h: 0.5,
+                },
+            },
+        ];
+        let mut poller = SwayPoller {
+            stream: client,
+            monitors: vec![
+                Monitor::new("DP-1", (0, 0), 1920, 1080),
+                Monitor::new("DP-2", (1920, 0), 1280, 720),
+            ],
+            placed: HashMap::new(),
+        };
+        poller.poll(&targets).unwrap();
+        // Windows that were already placed are left alone
+        poller.poll(&targets).unwrap();
+        poller.restore().unwrap();
+        drop(poller);
+        server.join().unwrap();
+
+        assert_eq!(
+            *commands.lock().unwrap(),
+            [
+                "[con_id=10] floating enable, border none, resize set width 1920 px height 540 px, move absolute position 0 px 0 px",
+                "[con_id=12] floating enable, border none, resize set width 640 px height 360 px, move absolute position 2560 px 360 px",
+                "[con_id=10] floating disable, border normal 2",
+                "[con_id=12] border pixel 3",
+            ]
+        );
+    }
+}
@@ -0,0 +1,297 @@
+use super::*;
+
+use std::collections::HashSet;
+
+use x11rb::connection::Connection;
+use x11rb::properties::WmClass;
+use x11rb::protocol::randr::ConnectionExt as _;
+use x11rb::protocol::xproto::*;
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+
+// X11 monitors are already in SDL's order, see monitors()
+pub fn connect(_monitors: &[Monitor]) -> Result<Box<dyn WindowPoller>, Box<dyn Error>> {
+    Ok(Box::new(X11Poller::connect(None)?))
+}
+
+// Looks for gamescope windows over X11 (or XWayland) and places them using EWMH hints,
+// for window managers that can't be scripted like KWin
+struct X11Poller {
+    conn: RustConnection,
+    root: Window,
+    net_client_list: Atom,
+    net_wm_pid: Atom,
+    net_wm_state: Atom,
+    net_wm_state_above: Atom,
+    motif_wm_hints: Atom,
+    // Windows that were already placed, along with the PID they were placed for
+    placed: HashSet<(Window, u32)>,
+}
+
+impl WindowPoller for X11Poller {
+    fn poll(&mut self, targets: &[WindowPlacement]) -> Result<(), Box<dyn Error>> {
+        let monitors = self.monitors()?;
+        for window in self.client_windows()? {
+            if !self.is_gamescope(window) {
+                continue;
+            }
+            let Some(pid) = self.window_pid(window) else {
+                continue;
+            };
+            let Some(target) = targets.iter().find(|t| t.pid == pid) else {
+                continue;
+            };
+            if self.placed.contains(&(window, pid)) {
+                continue;
+            }
+            let Some(&(mx, my, mw, mh)) = monitors.get(target.monitor) else {
+                continue;
+            };
+
+            let (x, y, w, h) = target.rect.to_pixels(mw, mh);
+            self.undecorate(window)?;
+            self.conn.configure_window(
+                window,
+                &ConfigureWindowAux::new()
+                    .x(mx + x)
+                    .y(my + y)
+                    .width(w)
+                    .height(h),
+            )?;
+            self.keep_above(window)?;
+            self.conn.flush()?;
+            self.placed.insert((window, pid));
+        }
+        Ok(())
+    }
+}
+
+impl X11Poller {
+    // Connects to the given display, or to $DISPLAY
+    fn connect(display: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let (conn, screen_num) = x11rb::connect(display)?;
+        let root = conn.setup().roots[screen_num].root;
+        let atom = |name: &[u8]| -> Result<Atom, Box<dyn Error>> {
+            Ok(conn.intern_atom(false, name)?.reply()?.atom)
+        };
+        Ok(Self {
+            root,
+            net_client_list: atom(b"_NET_CLIENT_LIST")?,
+            net_wm_pid: atom(b"_NET_WM_PID")?,
+            net_wm_state: atom(b"_NET_WM_STATE")?,
+            net_wm_state_above: atom(b"_NET_WM_STATE_ABOVE")?,
+            motif_wm_hints: atom(b"_MOTIF_WM_HINTS")?,
+            placed: HashSet::new(),
+            conn,
+        })
+    }
+
+    // Monitor geometries, in the same order as SDL (and so gamescope's --display-index) uses:
+    // the primary monitor first, then the others in RandR order
+    fn monitors(&self) -> Result<Vec<MonitorGeometry>, Box<dyn Error>> {
+        let mut monitors = self
+            .conn
+            .randr_get_monitors(self.root, true)?
+            .reply()?
+            .monitors;
+        monitors.sort_by_key(|m| !m.primary);
+        Ok(monitors
+            .iter()
+            .map(|m| (m.x as i32, m.y as i32, m.width as u32, m.height as u32))
+            .collect())
+    }
+
+    // Top-level windows, from the window manager's client list if there is one
+    fn client_windows(&self) -> Result<Vec<Window>, Box<dyn Error>> {
+        let reply = self
+            .conn
+            .get_property(
+                false,
+                self.root,
+                self.net_client_list,
+                AtomEnum::WINDOW,
+                0,
+                u32::MAX,
+            )?
+            .reply()?;
+        if let Some(windows) = reply.value32() {
+            return Ok(windows.collect());
+        }
+        Ok(self.conn.query_tree(self.root)?.reply()?.children)
+    }
+
+    fn is_gamescope(&self, window: Window) -> bool {
+        match WmClass::get(&self.conn, window).map(|cookie| cookie.reply()) {
+            Ok(Ok(Some(class))) => {
+                class.class() == b"gamescope" || class.class() == b"gamescope-kbm"
+            }
+            _ => false,
+        }
+    }
+
+    fn window_pid(&self, window: Window) -> Option<u32> {
+        let reply = self
+            .conn
+            .get_property(false, window, self.net_wm_pid, AtomEnum::CARDINAL, 0, 1)
+            .ok()?
+            .reply()
+            .ok()?;
+        reply.value32()?.next()
+    }
+
+    fn undecorate(&self, window: Window) -> Result<(), Box<dyn Error>> {
+        // flags = MWM_HINTS_DECORATIONS, decorations = none
+        self.conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            self.motif_wm_hints,
+            self.motif_wm_hints,
+            &[2, 0, 0, 0, 0],
+        )?;
+        Ok(())
+    }
+
+    fn keep_above(&self, window: Window) -> Result<(), Box<dyn Error>> {
+        // _NET_WM_STATE_ADD, sent from a normal application
+        let event = ClientMessageEvent::new(
+            32,
+            window,
+            self.net_wm_state,
+            [1, self.net_wm_state_above, 0, 1, 0],
+        );
+        self.conn.send_event(
+            false,
+            self.root,
+            EventMask::SUBSTRUCTURE_REDIRECT | EventMask::SUBSTRUCTURE_NOTIFY,
+            event,
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Rect;
+    use std::process::{Child, Command, Stdio};
+    use std::time::{Duration, Instant};
+
+    // A headless X server, killed when dropped
+    struct Xvfb(Child);
+
+    impl Drop for Xvfb {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    fn start_xvfb(display: &str) -> Xvfb {
+        let child = Command::new("Xvfb")
+            .args([display, "-screen", "0", "1280x720x24", "-nolisten", "tcp"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("Couldn't start Xvfb");
+        let xvfb = Xvfb(child);
+        let start = Instant::now();
+        while x11rb::connect(Some(display)).is_err() {
+            assert!(
+                start.elapsed() < Duration::from_secs(5),
+                "Xvfb didn't start"
+            );
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        xvfb
+    }
+
+    fn gamescope_window(poller: &X11Poller, pid: u32) -> Window {
+        let conn = &poller.conn;
+        let window = conn.generate_id().unwrap();
+        conn.create_window(
+            x11rb::COPY_DEPTH_FROM_PARENT,
+            window,
+            poller.root,
+            0,
+            0,
+            100,
+            100,
+            0,
+            WindowClass::INPUT_OUTPUT,
+            x11rb::COPY_FROM_PARENT,
+            &CreateWindowAux::new(),
+        )
+        .unwrap();
+        conn.change_property8(
+            PropMode::REPLACE,
+            window,
+            AtomEnum::WM_CLASS,
+            AtomEnum::STRING,
+            b"gamescope\0gamescope\0",
+        )
+        .unwrap();
+        conn.change_property32(
+            PropMode::REPLACE,
+            window,
+            poller.net_wm_pid,
+            AtomEnum::CARDINAL,
+            &[pid],
+        )
+        .unwrap();
+        conn.map_window(window).unwrap();
+        conn.flush().unwrap();
+        window
+    }
+
+    fn geometry(poller: &X11Poller, window: Window) -> (i16, i16, u16, u16) {
+        let geo = poller.conn.get_geometry(window).unwrap().reply().unwrap();
+        (geo.x, geo.y, geo.width, geo.height)
+    }
+
+    #[test]
+    #[ignore = "needs Xvfb; run with --ignored"]
+    fn places_gamescope_windows() {
+        let display = format!(":{}", 100 + std::process::id() % 900);
+        let _xvfb = start_xvfb(&display);
+
+        let mut poller = X11Poller::connect(Some(&display)).unwrap();
+        let top = gamescope_window(&poller, 100);
+        let bottom = gamescope_window(&poller, 200);
+        let other = gamescope_window(&poller, 300);
+
+        let targets = [
+            WindowPlacement {
+                pid: 100,
+                monitor: 0,
+                rect: Rect {
+                    x: 0.0,
+                    y: 0.0,
+                    w: 1.0,
+                    h: 0.5,
+                },
+            },
+            WindowPlacement {
+                pid: 200,
+                monitor: 0,
+                rect: Rect {
+                    x: 0.5,
+                    y: 0.5,
+                    w: 0.5,
+                    h: 0.5,
+                },
+            },
+        ];
+        poller.poll(&targets).unwrap();
+        assert_eq!(geometry(&poller, top), (0, 0, 1280, 360));
+        assert_eq!(geometry(&poller, bottom), (640, 360, 640, 360));
+        assert_eq!(geometry(&poller, other), (0, 0, 100, 100));
+
+        // Windows that were already placed are left alone
+        poller
+            .conn
+            .configure_window(top, &ConfigureWindowAux::new().x(10))
+            .unwrap();
+        poller.poll(&targets).unwrap();
+        assert_eq!(geometry(&poller, top), (10, 0, 1280, 360));
+    }
+}
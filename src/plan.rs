@@ -0,0 +1,896 @@
+use crate::app::{PadFilterType, PartyConfig};
+use crate::handler::*;
+use crate::input::*;
+use crate::instance::*;
+use crate::layout::Rect;
+use crate::paths::*;
+use crate::placement::placement_backend_name;
+use crate::util::*;
+
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Bump this whenever the layout of the serialized launch plan changes
+pub const LAUNCH_PLAN_VERSION: u16 = 3;
+
+#[derive(Serialize, Clone)]
+pub struct BindMount {
+    pub src: PathBuf,
+    pub dest: PathBuf,
+}
+
+#[derive(Serialize, Clone)]
+pub struct OverlayMount {
+    pub lowerdir: String,
+    pub upperdir: PathBuf,
+    pub workdir: PathBuf,
+    pub mountpoint: PathBuf,
+}
+
+#[derive(Serialize, Clone)]
+pub struct InstancePlan {
+    pub profile: String,
+    pub monitor: usize,
+    pub width: u32,
+    pub height: u32,
+    pub rect: Rect,
+
+    pub program: PathBuf,
+    // Kept as given, so paths that aren't valid UTF-8 reach the command intact;
+    // only the serialized plan converts them lossily
+    #[serde(serialize_with = "serialize_args")]
+    pub args: Vec<OsString>,
+    #[serde(serialize_with = "serialize_env")]
+    pub env: BTreeMap<String, OsString>,
+    pub cwd: PathBuf,
+
+    pub binds: Vec<BindMount>,
+    pub overlay: Option<OverlayMount>,
+    pub masked_devices: Vec<String>,
+    // Nodes of the player's virtual gamepad, the only input devices the instance sees
+    pub virtual_gamepad: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct LaunchPlan {
+    pub plan_version: u16,
+    pub partydeck_version: String,
+    pub handler: String,
+    pub dry_run: bool,
+    pub window_placement: Option<String>,
+    pub instances: Vec<InstancePlan>,
+    pub warnings: Vec<String>,
+}
+
+fn serialize_args<S: Serializer>(args: &[OsString], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(args.iter().map(|arg| arg.to_string_lossy()))
+}
+
+fn serialize_env<S: Serializer>(
+    env: &BTreeMap<String, OsString>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(env.iter().map(|(key, value)| (key, value.to_string_lossy())))
+}
+
+impl InstancePlan {
+    pub fn arg(&mut self, arg: impl AsRef<OsStr>) -> &mut Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        for arg in args {
+            self.arg(arg);
+        }
+        self
+    }
+
+    pub fn env(&mut self, key: &str, value: impl AsRef<OsStr>) -> &mut Self {
+        self.env.insert(key.to_string(), value.as_ref().to_os_string());
+        self
+    }
+
+    // Adds a bwrap --bind argument and records it
+    pub fn bind(&mut self, src: impl AsRef<Path>, dest: impl AsRef<Path>) -> &mut Self {
+        let (src, dest) = (src.as_ref(), dest.as_ref());
+        self.args(["--bind".as_ref(), src.as_os_str(), dest.as_os_str()]);
+        self.binds.push(BindMount {
+            src: src.to_path_buf(),
+            dest: dest.to_path_buf(),
+        });
+        self
+    }
+
+    // Hides an input device from the instance by binding /dev/null over it
+    pub fn mask_device(&mut self, path: &str) -> &mut Self {
+        self.bind("/dev/null", path);
+        self.masked_devices.push(path.to_string());
+        self
+    }
+
+    pub fn command(&self) -> Command {
+        let mut cmd = Command::new(&self.program);
+        cmd.current_dir(&self.cwd);
+        cmd.envs(&self.env);
+        cmd.args(&self.args);
+        cmd
+    }
+}
+
+impl LaunchPlan {
+    pub fn commands(&self) -> Vec<Command> {
+        self.instances.iter().map(|i| i.command()).collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+pub struct LaunchPaths {
+    pub party: PathBuf,
+    pub steam: PathBuf,
+    pub res: PathBuf,
+    pub umu_run: PathBuf,
+    pub gsc_kbm: PathBuf,
+}
+
+// Everything the plan builder needs to know about the host system.
+// Building a plan through this trait keeps it free of side effects, so it can be
+// computed against a fake filesystem as well as the real one.
+pub trait LaunchEnv {
+    fn paths(&self) -> &LaunchPaths;
+    fn game_rootpath(&self, h: &Handler) -> Result<PathBuf, Box<dyn Error>>;
+    fn exists(&self, path: &Path) -> bool;
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf>;
+}
+
+pub struct HostEnv {
+    paths: LaunchPaths,
+}
+
+impl HostEnv {
+    pub fn new() -> Self {
+        Self {
+            paths: LaunchPaths {
+                party: PATH_PARTY.clone(),
+                steam: PATH_STEAM.clone(),
+                res: PATH_RES.clone(),
+                umu_run: BIN_UMU_RUN.clone(),
+                gsc_kbm: BIN_GSC_KBM.clone(),
+            },
+        }
+    }
+}
+
+impl LaunchEnv for HostEnv {
+    fn paths(&self) -> &LaunchPaths {
+        &self.paths
+    }
+
+    fn game_rootpath(&self, h: &Handler) -> Result<PathBuf, Box<dyn Error>> {
+        h.get_game_rootpath().map(PathBuf::from)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+        std::fs::read_link(path)
+    }
+}
+
+pub fn mount_gamedirs(h: &Handler, cfg: &PartyConfig) -> bool {
+    h.is_saved_handler() && !cfg.disable_mount_gamedirs && cfg.profile_unique_dirs
+}
+
+// Builds everything needed to launch the instances without spawning anything.
+// With dry_run set, problems that would normally abort the launch (missing game files,
+// runtimes or Steam SDK links) are recorded as warnings instead, and paths that only
+// exist once the game directories are mounted are checked against the game's root folder.
+// virtual_pads holds each instance's virtual gamepad nodes when cfg.virtual_gamepads is set.
+pub fn build_launch_plan(
+    h: &Handler,
+    input_devices: &[DeviceInfo],
+    instances: &[Instance],
+    cfg: &PartyConfig,
+    virtual_pads: &[Vec<String>],
+    env: &dyn LaunchEnv,
+    dry_run: bool,
+) -> Result<LaunchPlan, Box<dyn Error>> {
+    let paths = env.paths();
+    let win = h.win();
+    let exec = Path::new(&h.exec);
+    let runtime = h.runtime.as_str();
+    let gamescope = match cfg.kbm_support {
+        true => paths.gsc_kbm.as_path(),
+        false => Path::new("gamescope"),
+    };
+
+    let mut plan = LaunchPlan {
+        plan_version: LAUNCH_PLAN_VERSION,
+        partydeck_version: env!("CARGO_PKG_VERSION").to_string(),
+        handler: h.handler_dir_name().to_string(),
+        dry_run,
+        window_placement: placement_backend_name(cfg).map(str::to_string),
+        instances: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    // Fails the launch, or only records the problem when doing a dry run
+    let problem = |plan: &mut LaunchPlan, err: String| -> Result<(), Box<dyn Error>> {
+        if dry_run {
+            plan.warnings.push(err);
+            Ok(())
+        } else {
+            Err(err.into())
+        }
+    };
+
+    if (runtime == "scout" && !env.exists(&paths.steam.join("bin32/steam-runtime/run.sh")))
+        || (runtime == "soldier"
+            && !env.exists(&paths.steam.join("steam/steamapps/common/SteamLinuxRuntime_soldier")))
+    {
+        problem(&mut plan, format!("Steam Runtime {runtime} not found!"))?;
+    }
+
+    let mount = mount_gamedirs(h, cfg);
+    let gameroot = match env.game_rootpath(h) {
+        Ok(path) => path,
+        Err(e) if dry_run => {
+            plan.warnings.push(format!("{e}; using {}", h.path_gameroot));
+            PathBuf::from(&h.path_gameroot)
+        }
+        Err(e) => return Err(e),
+    };
+    let overlays = match mount {
+        true => Some(overlay_mounts(h, instances, &gameroot, env)),
+        false => None,
+    };
+
+    for (i, instance) in instances.iter().enumerate() {
+        let gamedir = match mount {
+            true => paths.party.join("tmp").join(format!("game-{}", i)),
+            false => gameroot.clone(),
+        };
+        // The mounted game directories don't exist during a dry run
+        let checkdir = match dry_run {
+            true => &gameroot,
+            false => &gamedir,
+        };
+
+        if !env.exists(&checkdir.join(exec)) {
+            problem(&mut plan, format!("Executable not found: {}", checkdir.join(exec).display()))?;
+        }
+
+        for id in &instance.devices {
+            if !input_devices.iter().any(|dev| &dev.id == id) {
+                plan.warnings.push(format!("Player {}: device {} is not connected", i + 1, id));
+            }
+        }
+
+        let path_exec = gamedir.join(exec);
+        let cwd = path_exec.parent().ok_or("couldn't get parent")?;
+
+        let path_prof = paths.party.join("profiles").join(&instance.profname);
+        if !cfg.virtual_gamepads && env.exists(&path_prof.join("remap.json")) {
+            plan.warnings.push(format!(
+                "Player {}: {}'s button remapping only applies with virtual gamepads",
+                i + 1,
+                instance.profname
+            ));
+        }
+        let path_pfx = paths
+            .party
+            .join("prefixes")
+            .join(match cfg.proton_separate_pfxs {
+                true => (i + 1).to_string(),
+                false => "1".to_string(),
+            });
+
+        let mut cmd = InstancePlan {
+            profile: instance.profname.clone(),
+            monitor: instance.monitor,
+            width: instance.width,
+            height: instance.height,
+            rect: instance.rect,
+            program: gamescope.to_path_buf(),
+            args: Vec::new(),
+            env: BTreeMap::new(),
+            cwd: cwd.to_path_buf(),
+            binds: Vec::new(),
+            overlay: overlays.as_ref().map(|o| o[i].clone()),
+            masked_devices: Vec::new(),
+            virtual_gamepad: virtual_pads.get(i).cloned().unwrap_or_default(),
+        };
+
+        cmd.env("SDL_JOYSTICK_HIDAPI", "0");
+        cmd.env("ENABLE_GAMESCOPE_WSI", "0");
+        if h.sdl2_override != SDL2Override::No {
+            let path_sdl = match h.sdl2_override {
+                SDL2Override::Srt => paths
+                    .steam
+                    .join("bin32/steam-runtime/usr/lib/i386-linux-gnu/libSDL2-2.0.so.0"),
+                SDL2Override::Sys => PathBuf::from("/usr/lib/libSDL2.so"),
+                _ => PathBuf::new(),
+            };
+            cmd.env("SDL_DYNAMIC_API", path_sdl);
+        }
+        if win {
+            let protonpath = match cfg.proton_version.is_empty() {
+                true => "GE-Proton",
+                false => &cfg.proton_version,
+            };
+
+            cmd.env("WINEPREFIX", &path_pfx);
+            cmd.env("PROTON_VERB", "run");
+            cmd.env("PROTONPATH", protonpath);
+            cmd.env("PROTON_DISABLE_HIDRAW", "1");
+            if cfg.proton_wow64 {
+                cmd.env("PROTON_USE_WOW64", "1");
+            }
+        }
+        if cfg.pad_filter_type != PadFilterType::NoSteamInput {
+            cmd.env("SDL_GAMECONTROLLER_ALLOW_STEAM_VIRTUAL_GAMEPAD", "1");
+        }
+        if cfg.pad_filter_type == PadFilterType::OnlySteamInput {
+            cmd.env("SDL_GAMECONTROLLER_IGNORE_DEVICES", SDL_GAMECONTROLLER_IGNORE_DEVICES);
+        }
+        if !h.env.is_empty() {
+            for env_var in h.env.split_whitespace() {
+                if let Some((key, value)) = env_var.split_once('=') {
+                    cmd.env(key, value);
+                }
+            }
+        }
+
+        // Gamescope args
+        cmd.args([
+            "-W",
+            &instance.width.to_string(),
+            "-H",
+            &instance.height.to_string(),
+        ]);
+        if cfg.gamescope_force_grab_cursor {
+            cmd.arg("--force-grab-cursor");
+        }
+        if cfg.gamescope_sdl_backend {
+            cmd.arg("--backend=sdl");
+            cmd.arg(format!("--display-index={}", instance.monitor));
+        }
+        if cfg.kbm_support {
+            let mut instance_has_keyboard = false;
+            let mut instance_has_mouse = false;
+            let mut kbms = String::new();
+
+            for dev in input_devices.iter().filter(|dev| instance.devices.contains(&dev.id)) {
+                // A keyboard used as a gamepad is fed to the instance's virtual gamepad instead
+                if dev.device_type == DeviceType::Keyboard && instance.has_keyboard_gamepad(cfg) {
+                    continue;
+                }
+                if dev.device_type == DeviceType::Keyboard {
+                    instance_has_keyboard = true;
+                } else if dev.device_type == DeviceType::Mouse {
+                    instance_has_mouse = true;
+                }
+                if dev.device_type == DeviceType::Keyboard || dev.device_type == DeviceType::Mouse {
+                    kbms.push_str(&format!("{},", &dev.path));
+                }
+            }
+
+            if instance_has_keyboard {
+                cmd.arg("--backend-disable-keyboard");
+            }
+            if instance_has_mouse {
+                cmd.arg("--backend-disable-mouse");
+            }
+            if !kbms.is_empty() {
+                cmd.arg(format!("--libinput-hold-dev={}", kbms));
+            }
+        }
+        cmd.arg("--");
+
+        // Bwrap args
+        cmd.arg("bwrap");
+        cmd.arg("--die-with-parent");
+        cmd.args(["--dev-bind", "/", "/"]);
+        cmd.args(["--tmpfs", "/tmp"]);
+        if cfg.virtual_gamepads {
+            // Only the player's virtual gamepad is left in /dev/input
+            cmd.args(["--tmpfs", "/dev/input"]);
+            for node in cmd.virtual_gamepad.clone() {
+                cmd.args(["--dev-bind", &node, &node]);
+            }
+            if dry_run && virtual_pads.is_empty() {
+                plan.warnings.push(format!(
+                    "Player {}: virtual gamepads are only created at launch",
+                    i + 1
+                ));
+            }
+        }
+        // Mask out any controllers that aren't this player's, along with all of their other
+        // nodes, except for nodes that also belong to one of this player's devices. With
+        // virtual gamepads, the physical controllers' hidraw nodes are all masked instead.
+        let owned: Vec<&String> = input_devices
+            .iter()
+            .filter(|dev| dev.enabled && instance.devices.contains(&dev.id))
+            .filter(|_| !cfg.virtual_gamepads)
+            .flat_map(|dev| std::iter::once(&dev.path).chain(dev.nodes.iter()))
+            .collect();
+        for dev in input_devices {
+            if !dev.enabled
+                || (cfg.virtual_gamepads && dev.device_type.is_controller())
+                || (!instance.devices.contains(&dev.id) && dev.device_type.is_controller())
+            {
+                for path in std::iter::once(&dev.path).chain(dev.nodes.iter()) {
+                    // /dev/input is already replaced with just the virtual gamepad
+                    let hidden = cfg.virtual_gamepads && path.starts_with("/dev/input/");
+                    if !hidden && !owned.contains(&path) && !cmd.masked_devices.contains(path) {
+                        cmd.mask_device(path);
+                    }
+                }
+            }
+        }
+
+        if cfg.profile_unique_dirs {
+            if win {
+                let path_pfx_user = path_pfx.join("drive_c/users/steamuser");
+                cmd.bind(path_prof.join("windata"), &path_pfx_user);
+            } else {
+                let path_prof_home = path_prof.join("home");
+                cmd.env("HOME", &path_prof_home);
+                // Also bind the Steam directory as the Steam runtimes look for HOME/.steam
+                if !runtime.is_empty() || h.steam_appid.is_some() {
+                    cmd.bind(&paths.steam, path_prof_home.join(".steam"));
+                }
+            }
+        }
+
+        for subpath in &h.game_null_paths {
+            let game_subpath = gamedir.join(subpath);
+            let check_subpath = checkdir.join(subpath);
+            if env.is_file(&check_subpath) {
+                cmd.bind("/dev/null", &game_subpath);
+            } else if env.is_dir(&check_subpath) {
+                cmd.bind(paths.party.join("tmp/null"), &game_subpath);
+            }
+        }
+
+        if h.use_goldberg {
+            cmd.env("GseAppPath", paths.party.join("goldberg_data"));
+            cmd.env("GseSavePath", path_prof.join("steam"));
+            cmd.env("SteamAppUser", &instance.profname);
+            cmd.env("SteamUser", &instance.profname);
+            cmd.env("SteamClientLaunch", "1");
+            cmd.env("SteamEnv", "1");
+            if let Some(appid) = h.steam_appid {
+                cmd.env("SteamAppId", appid.to_string());
+                cmd.env("SteamGameId", appid.to_string());
+            }
+
+            for (sdk, goldberg) in [("sdk32", "goldberg/linux32"), ("sdk64", "goldberg/linux64")] {
+                match env.read_link(&paths.steam.join(sdk)) {
+                    Ok(sdk_link) => {
+                        cmd.bind(paths.res.join(goldberg), sdk_link);
+                    }
+                    Err(e) => problem(&mut plan, format!("Failed to read {sdk} link: {e}"))?,
+                }
+            }
+
+            if win {
+                cmd.bind(
+                    paths.res.join("goldberg/win"),
+                    path_pfx.join("drive_c/Program Files (x86)/Steam"),
+                );
+            }
+        }
+
+        // Runtime
+        if win {
+            cmd.arg(&paths.umu_run);
+        } else {
+            match runtime {
+                "scout" => {
+                    cmd.arg(paths.steam.join("bin32/steam-runtime/run.sh"));
+                }
+                "soldier" => {
+                    cmd.arg(
+                        paths
+                            .steam
+                            .join("steam/steamapps/common/SteamLinuxRuntime_soldier/_v2-entry-point"),
+                    );
+                    cmd.arg("--");
+                }
+                _ => {}
+            };
+        }
+
+        cmd.arg(&path_exec);
+
+        for arg in h.args.split_whitespace() {
+            let processed_arg = match arg {
+                "$PROFILE" => &instance.profname,
+                "$WIDTH" => &instance.width.to_string(),
+                "$HEIGHT" => &instance.height.to_string(),
+                "$RESOLUTION" => &format!("{}x{}", instance.width, instance.height),
+                "$INSTANCECOUNT" => &instances.len().to_string(),
+                "$INSTANCENUM" => &i.to_string(),
+                "$GAMEDIR" => &gamedir.os_fmt(win),
+                "$HANDLERDIR" => &h.path_handler.os_fmt(win),
+                _ => &String::from(arg).sanitize_path(),
+            };
+            cmd.arg(processed_arg);
+        }
+
+        plan.instances.push(cmd);
+    }
+
+    Ok(plan)
+}
+
+// Gets the fuse-overlayfs mounts giving each instance its own writable copy of the game directory
+pub fn overlay_mounts(
+    h: &Handler,
+    instances: &[Instance],
+    gameroot: &Path,
+    env: &dyn LaunchEnv,
+) -> Vec<OverlayMount> {
+    let paths = env.paths();
+    let tmp_dir = paths.party.join("tmp");
+    let mut path_lowerdir = gameroot.to_string_lossy().to_string();
+
+    let overlay_path = h.path_handler.join("overlay");
+    if env.exists(&overlay_path) {
+        path_lowerdir = format!("{}:{}", overlay_path.display(), path_lowerdir);
+    }
+
+    let gamename = h.handler_dir_name().to_string();
+
+    instances
+        .iter()
+        .enumerate()
+        .map(|(i, instance)| {
+            let path_prof = paths.party.join("profiles").join(&instance.profname);
+            OverlayMount {
+                lowerdir: path_lowerdir.clone(),
+                upperdir: path_prof.join("gamesaves").join(&gamename),
+                workdir: tmp_dir.join(format!("work-{}", i)),
+                mountpoint: tmp_dir.join(format!("game-{}", i)),
+            }
+        })
+        .collect()
+}
+
+const SDL_GAMECONTROLLER_IGNORE_DEVICES: &str = "0x054c/0x0df2,0x054c/0x0df2,0x045e/0x02e3,0x045e/0x0b00,0x045e/0x0b05,0x2dc8/0x6000,0x2dc8/0x6100,0x2dc8/0x6001,0x2dc8/0x6101,0x2dc8/0x6003,0x2dc8/0x6006,0x2dc8/0x6009,0x2dc8/0x6012,0x28de/0x1002,0x28de/0x1003,0x28de/0x1071,0x28de/0x1052,0x28de/0x1042,0x28de/0x1203,0x28de/0x1204,0x28de/0x1205,0x28de/0x1206,0x28de/0x1302,0x28de/0x1303,0x28de/0x1304,0x28de/0x1305,0x0f0d/0x01ab,0x0f0d/0x0196,0x28de/0x12ff,0x28de/0x12fe,0x28de/0x12fd,0x28de/0x12fc,0x28de/0x12fb,0x28de/0x12fa,0x28de/0x12f9,0x28de/0x12f8,0x28de/0x12f7,0x28de/0x12f6,0x28de/0x12f5,0x28de/0x12f4,0x28de/0x12f3,0x28de/0x12f2,0x28de/0x12f1,0x28de/0x12f0,0x0079/0x181a,0x044f/0xb315,0x044f/0xd007,0x046d/0xcad1,0x054c/0x0268,0x056e/0x200f,0x056e/0x2013,0x05b8/0x1004,0x05b8/0x1006,0x06a3/0xf622,0x0738/0x3180,0x0738/0x3250,0x0738/0x3481,0x0738/0x8180,0x0738/0x8838,0x0810/0x0001,0x0810/0x0003,0x0925/0x0005,0x0925/0x8866,0x0925/0x8888,0x0e6f/0x0109,0x0e6f/0x011e,0x0e6f/0x0128,0x0e6f/0x0214,0x0e6f/0x1314,0x0e6f/0x6302,0x0e8f/0x0008,0x0e8f/0x3075,0x0e8f/0x310d,0x0f0d/0x0009,0x0f0d/0x004d,0x0f0d/0x005f,0x0f0d/0x006a,0x0f0d/0x006e,0x0f0d/0x0085,0x0f0d/0x0086,0x0f0d/0x0088,0x0f30/0x1100,0x11ff/0x3331,0x1345/0x1000,0x1345/0x6005,0x146b/0x5500,0x1a34/0x0836,0x20bc/0x5500,0x20d6/0x576d,0x20d6/0xca6d,0x2563/0x0523,0x2563/0x0575,0x25f0/0x83c3,0x25f0/0xc121,0x2c22/0x2003,0x2c22/0x2302,0x2c22/0x2502,0x8380/0x0003,0x8888/0x0308,0x0079/0x181b,0x044f/0xd00e,0x054c/0x05c4,0x054c/0x05c5,0x054c/0x09cc,0x054c/0x0ba0,0x0738/0x8250,0x0738/0x8384,0x0738/0x8480,0x0738/0x8481,0x0c12/0x0e10,0x0c12/0x0e13,0x0c12/0x0e15,0x0c12/0x0e20,0x0c12/0x0ef6,0x0c12/0x1cf6,0x0c12/0x1e10,0x0c12/0x2e18,0x0e6f/0x0203,0x0e6f/0x0207,0x0e6f/0x020a,0x0f0d/0x0055,0x0f0d/0x005e,0x0f0d/0x0066,0x0f0d/0x0084,0x0f0d/0x0087,0x0f0d/0x008a,0x0f0d/0x009c,0x0f0d/0x00a0,0x0f0d/0x00ee,0x0f0d/0x011c,0x0f0d/0x0123,0x0f0d/0x0162,0x11c0/0x4001,0x146b/0x0d01,0x146b/0x0d02,0x146b/0x0d06,0x146b/0x0d08,0x146b/0x0d09,0x146b/0x0d10,0x146b/0x0d10,0x146b/0x0d13,0x146b/0x1103,0x1532/0x0401,0x1532/0x1000,0x1532/0x1004,0x1532/0x1007,0x1532/0x1008,0x1532/0x1009,0x1532/0x100a,0x1532/0x1100,0x20d6/0x792a,0x2c22/0x2000,0x2c22/0x2300,0x2c22/0x2500,0x3285/0x0d16,0x3285/0x0d17,0x7545/0x0104,0x9886/0x0025,0x054c/0x0ce6,0x054c/0x0df2,0x054c/0x0e5f,0x0e6f/0x0209,0x0f0d/0x0163,0x0f0d/0x0184,0x1532/0x100b,0x1532/0x100c,0x1532/0x1012,0x3285/0x0d18,0x3285/0x0d19,0x358a/0x0104,0x0079/0x18d4,0x03eb/0xff02,0x044f/0xb326,0x045e/0x028e,0x045e/0x028f,0x045e/0x0291,0x045e/0x02a0,0x045e/0x02a1,0x045e/0x02a9,0x045e/0x0719,0x046d/0xc21d,0x046d/0xc21e,0x046d/0xc21f,0x046d/0xc242,0x056e/0x2004,0x0738/0x4716,0x0738/0x4718,0x0738/0x4726,0x0738/0x4728,0x0738/0x4736,0x0738/0x4738,0x0738/0x4740,0x0738/0xb726,0x0738/0xbeef,0x0738/0xcb02,0x0738/0xcb03,0x0738/0xf738,0x0955/0x7210,0x0955/0xb400,0x0b05/0x1b4c,0x0e6f/0x0105,0x0e6f/0x0113,0x0e6f/0x011f,0x0e6f/0x0125,0x0e6f/0x0127,0x0e6f/0x0131,0x0e6f/0x0133,0x0e6f/0x0143,0x0e6f/0x0147,0x0e6f/0x0201,0x0e6f/0x0213,0x0e6f/0x021f,0x0e6f/0x0301,0x0e6f/0x0313,0x0e6f/0x0314,0x0e6f/0x0401,0x0e6f/0x0413,0x0e6f/0x0501,0x0e6f/0xf900,0x0f0d/0x000a,0x0f0d/0x000c,0x0f0d/0x000d,0x0f0d/0x0016,0x0f0d/0x001b,0x0f0d/0x008c,0x0f0d/0x00db,0x0f0d/0x011e,0x1038/0x1430,0x1038/0x1431,0x1038/0xb360,0x11c9/0x55f0,0x12ab/0x0004,0x12ab/0x0301,0x12ab/0x0303,0x1430/0x02a0,0x1430/0x4748,0x1430/0xf801,0x146b/0x0601,0x15e4/0x3f00,0x15e4/0x3f0a,0x15e4/0x3f10,0x162e/0xbeef,0x1689/0xfd00,0x1689/0xfd01,0x1689/0xfe00,0x1949/0x041a,0x1bad/0x0002,0x1bad/0x0003,0x1bad/0xf016,0x1bad/0xf018,0x1bad/0xf019,0x1bad/0xf021,0x1bad/0xf023,0x1bad/0xf025,0x1bad/0xf027,0x1bad/0xf028,0x1bad/0xf02e,0x1bad/0xf036,0x1bad/0xf038,0x1bad/0xf039,0x1bad/0xf03a,0x1bad/0xf03d,0x1bad/0xf03e,0x1bad/0xf03f,0x1bad/0xf042,0x1bad/0xf080,0x1bad/0xf501,0x1bad/0xf502,0x1bad/0xf503,0x1bad/0xf504,0x1bad/0xf505,0x1bad/0xf506,0x1bad/0xf900,0x1bad/0xf901,0x1bad/0xf902,0x1bad/0xf903,0x1bad/0xf904,0x1bad/0xf906,0x1bad/0xfa01,0x1bad/0xfd00,0x1bad/0xfd01,0x24c6/0x5000,0x24c6/0x5300,0x24c6/0x5303,0x24c6/0x530a,0x24c6/0x531a,0x24c6/0x5397,0x24c6/0x5500,0x24c6/0x5501,0x24c6/0x5502,0x24c6/0x5503,0x24c6/0x5506,0x24c6/0x550d,0x24c6/0x550e,0x24c6/0x5508,0x24c6/0x5510,0x24c6/0x5b00,0x24c6/0x5b02,0x24c6/0x5b03,0x24c6/0x5d04,0x24c6/0xfafa,0x24c6/0xfafb,0x24c6/0xfafc,0x24c6/0xfafd,0x24c6/0xfafe,0x03f0/0x0495,0x044f/0xd012,0x045e/0x02d1,0x045e/0x02dd,0x045e/0x02e0,0x045e/0x02e3,0x045e/0x02ea,0x045e/0x02fd,0x045e/0x02ff,0x045e/0x0b00,0x045e/0x0b05,0x045e/0x0b0a,0x045e/0x0b0c,0x045e/0x0b12,0x045e/0x0b13,0x045e/0x0b20,0x045e/0x0b21,0x045e/0x0b22,0x0738/0x4a01,0x0e6f/0x0139,0x0e6f/0x013b,0x0e6f/0x013a,0x0e6f/0x0145,0x0e6f/0x0146,0x0e6f/0x015b,0x0e6f/0x015c,0x0e6f/0x015d,0x0e6f/0x015f,0x0e6f/0x0160,0x0e6f/0x0161,0x0e6f/0x0162,0x0e6f/0x0163,0x0e6f/0x0164,0x0e6f/0x0165,0x0e6f/0x0166,0x0e6f/0x0167,0x0e6f/0x0205,0x0e6f/0x0206,0x0e6f/0x0246,0x0e6f/0x0261,0x0e6f/0x0262,0x0e6f/0x02a0,0x0e6f/0x02a1,0x0e6f/0x02a2,0x0e6f/0x02a3,0x0e6f/0x02a4,0x0e6f/0x02a5,0x0e6f/0x02a6,0x0e6f/0x02a7,0x0e6f/0x02a8,0x0e6f/0x02a9,0x0e6f/0x02aa,0x0e6f/0x02ab,0x0e6f/0x02ac,0x0e6f/0x02ad,0x0e6f/0x02ae,0x0e6f/0x02af,0x0e6f/0x02b0,0x0e6f/0x02b1,0x0e6f/0x02b3,0x0e6f/0x02b5,0x0e6f/0x02b6,0x0e6f/0x02bd,0x0e6f/0x02be,0x0e6f/0x02bf,0x0e6f/0x02c0,0x0e6f/0x02c1,0x0e6f/0x02c2,0x0e6f/0x02c3,0x0e6f/0x02c4,0x0e6f/0x02c5,0x0e6f/0x02c6,0x0e6f/0x02c7,0x0e6f/0x02c8,0x0e6f/0x02c9,0x0e6f/0x02ca,0x0e6f/0x02cb,0x0e6f/0x02cd,0x0e6f/0x02ce,0x0e6f/0x02cf,0x0e6f/0x02d5,0x0e6f/0x0346,0x0e6f/0x0446,0x0e6f/0x02da,0x0e6f/0x02d6,0x0e6f/0x02d9,0x0f0d/0x0063,0x0f0d/0x0067,0x0f0d/0x0078,0x0f0d/0x00c5,0x0f0d/0x0150,0x10f5/0x7009,0x10f5/0x7013,0x1532/0x0a00,0x1532/0x0a03,0x1532/0x0a14,0x1532/0x0a15,0x20d6/0x2001,0x20d6/0x2002,0x20d6/0x2003,0x20d6/0x2004,0x20d6/0x2005,0x20d6/0x2006,0x20d6/0x2009,0x20d6/0x200a,0x20d6/0x200b,0x20d6/0x200c,0x20d6/0x200d,0x20d6/0x200e,0x20d6/0x200f,0x20d6/0x2011,0x20d6/0x2012,0x20d6/0x2015,0x20d6/0x2016,0x20d6/0x2017,0x20d6/0x2018,0x20d6/0x2019,0x20d6/0x201a,0x20d6/0x4001,0x20d6/0x4002,0x20d6/0x890b,0x24c6/0x541a,0x24c6/0x542a,0x24c6/0x543a,0x24c6/0x551a,0x24c6/0x561a,0x24c6/0x581a,0x24c6/0x591a,0x24c6/0x592a,0x24c6/0x791a,0x2dc8/0x2002,0x2dc8/0x3106,0x2e24/0x0652,0x2e24/0x1618,0x2e24/0x1688,0x146b/0x0611,0x0000/0x0000,0x045e/0x02a2,0x0e6f/0x1414,0x0e6f/0x0159,0x24c6/0xfaff,0x0f0d/0x006d,0x0f0d/0x00a4,0x0079/0x1832,0x0079/0x187f,0x0079/0x1883,0x03eb/0xff01,0x0c12/0x0ef8,0x046d/0x1000,0x11ff/0x0511,0x1345/0x6006,0x056e/0x2012,0x146b/0x0602,0x0f0d/0x00ae,0x046d/0x0401,0x046d/0x0301,0x046d/0xcaa3,0x046d/0xc261,0x046d/0x0291,0x0079/0x18d3,0x0f0d/0x00b1,0x0001/0x0001,0x0079/0x188e,0x0079/0x187c,0x0079/0x189c,0x0079/0x1874,0x2f24/0x0050,0x2f24/0x002e,0x2f24/0x0091,0x1430/0x0719,0x0f0d/0x00ed,0x0f0d/0x00c0,0x0e6f/0x0152,0x046d/0x1007,0x0e6f/0x02b8,0x0079/0x18a1,0x0000/0x6686,0x12ab/0x0304,0x1430/0x0291,0x1430/0x02a9,0x1430/0x070b,0x1bad/0x028e,0x1bad/0x02a0,0x1bad/0x5500,0x20ab/0x55ef,0x24c6/0x5509,0x2516/0x0069,0x25b1/0x0360,0x2c22/0x2203,0x2f24/0x0011,0x2f24/0x0053,0x2f24/0x00b7,0x046d/0x0000,0x046d/0x1004,0x046d/0x1008,0x046d/0xf301,0x0738/0x02a0,0x0738/0x7263,0x0738/0xb738,0x0738/0xcb29,0x0738/0xf401,0x0079/0x18c2,0x0079/0x18c8,0x0079/0x18cf,0x0c12/0x0e17,0x0c12/0x0e1c,0x0c12/0x0e22,0x0c12/0x0e30,0xd2d2/0xd2d2,0x0d62/0x9a1a,0x0d62/0x9a1b,0x0e00/0x0e00,0x0e6f/0x012a,0x0e6f/0x02b2,0x0f0d/0x0097,0x0f0d/0x00ba,0x0f0d/0x00d8,0x0fff/0x02a1,0x045e/0x0867,0x16d0/0x0f3f,0x2f24/0x008f,0x0e6f/0xf501,0x057e/0x2006,0x057e/0x2067,0x057e/0x2007,0x057e/0x2066,0x057e/0x2008,0x057e/0x2068,0x057e/0x2009,0x057e/0x2069,0x0f0d/0x00c1,0x0f0d/0x0092,0x0f0d/0x00f6,0x0e6f/0x0180,0x0e6f/0x0181,0x0e6f/0x0184,0x0e6f/0x0185,0x0e6f/0x0186,0x0e6f/0x0187,0x0e6f/0x0188,0x0e6f/0x018c,0x0f0d/0x00aa,0x20d6/0xa711,0x20d6/0xa712,0x20d6/0xa713,0x20d6/0xa714,0x20d6/0xa715,0x20d6/0xa716,0x20d6/0xa718,0x33dd/0x0001,0x33dd/0x0002,0x33dd/0x0003,0x0f0d/0x00f0,0x0000/0x11fb,0x28de/0x1101,0x28de/0x1102,0x28de/0x1105,0x28de/0x1106,0x28de/0x1142,0x28de/0x1201,0x28de/0x1202,0x28de/0x1205,0x28de/0x1302,0x28de/0x1303,0x28de/0x1304,0x2dc8/0x9000,0x2dc8/0x3810,0x2dc8/0x0651,0x2dc8/0x9020,0x2dc8/0x9015,0x2dc8/0x2865,0x1235/0xab12,0x2002/0x9000,0x2dc8/0x9001,0x3820/0x0009,0x2dc8/0x3820,0x2dc8/0x2000,0x2dc8/0x2000,0x2810/0x0009,0x2dc8/0x2830,0x2dc8/0x6002,0x2dc8/0x6102,0x1235/0xab20,0x2820/0x0009,0x2dc8/0x301b,0x2dc8/0x3011,0x2dc8/0x3013,0x2dc8/0x9018,0x2dc8/0x3230,0x05a0/0x3232,0x05a0/0x3232,0x2dc8/0x3100,0x2dc8/0x9012,0x2dc8/0x2862,0x0b05/0x4500,0x0b05/0x4500,0x0b05/0x7905,0x0b05/0x7906,0x0010/0x0082,0x1949/0x0402,0x1949/0x0419,0x0171/0x0419,0x0079/0x1830,0x3250/0x1001,0x3250/0x1001,0x3250/0x1002,0x3250/0x1002,0x24c6/0x891b,0x0c12/0x0ef7,0x04b4/0x010a,0xffff/0xffff,0x20e8/0x5860,0x0926/0x8888,0x0e6f/0x0130,0x0079/0x0011,0x1a34/0xf705,0x1949/0x0402,0x3537/0x1097,0x05ac/0x061a,0x25f0/0x83c1,0x18d1/0x9400,0x18d1/0x9400,0x0428/0x4001,0x0e8f/0x1006,0x0e8f/0x0012,0x0f0d/0x0010,0x0f0d/0x0022,0x0f0d/0x006b,0xdead/0xbeef,0x14d8/0x6208,0x0e8f/0x3013,0x04d8/0x0082,0x05fd/0x3000,0x1949/0x0402,0x056e/0x2003,0x0f30/0x0110,0x22ba/0x1020,0x046d/0xc219,0x046d/0xc216,0x046d/0xc216,0x046d/0xc219,0x046d/0xc218,0x046d/0xc211,0x24c6/0x892b,0x24c6/0x892a,0x24c6/0x891a,0x0738/0x5266,0x0738/0x3384,0x0738/0x3480,0x0738/0x8818,0x0078/0x0006,0x045e/0x000e,0x045e/0x0285,0x045e/0x0289,0x045e/0x0289,0x20d6/0x0dad,0x146b/0x0c01,0x0810/0xe501,0x0955/0x7214,0x0955/0x7214,0x124b/0x4d01,0x1345/0x3008,0x0079/0x1843,0x0079/0x1844,0x057e/0x2019,0x057e/0x2019,0x057e/0x201e,0x057e/0x2017,0x057e/0x2017,0x057e/0x2017,0x057e/0x0306,0x057e/0x0330,0x057e/0x0306,0x050d/0x0803,0x2836/0x0001,0x2836/0x0001,0x045e/0x0202,0x11ff/0x3341,0x0e8f/0x0003,0x054c/0x0cda,0x0f30/0x1112,0x2c22/0x2012,0x2c22/0x2010,0x1532/0x0402,0x1532/0x0705,0x1532/0x0900,0x1532/0x0900,0xf000/0x0003,0x0079/0x0011,0x1a34/0x0809,0x7545/0x1122,0x06a3/0xf623,0x06a3/0xff0c,0x06a3/0x040c,0x06a3/0x0109,0x06a3/0x040b,0x06a3/0xf518,0x16c0/0x0487,0x28de/0x11fc,0x0111/0x1431,0x0111/0x1419,0x6666/0x8804,0xf000/0x00f1,0x044f/0xb320,0x044f/0xb323,0x044f/0xb300,0x044f/0xd009,0x044f/0xd008,0x12bd/0xd015,0x14d8/0xcd07,0x0079/0x0011,0x05ac/0x3232,0x0c45/0x4320,0x2717/0x3144,0x16c0/0x05e1,0x6666/0x0667,0x0583/0x2060,0x07b5/0x0315,0x289b/0x0080,0x289b/0x0003,0x289b/0x0060,";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashMap};
+
+    // A host with a fixed set of files, so plans come out the same on any machine
+    struct FakeEnv {
+        paths: LaunchPaths,
+        gameroot: PathBuf,
+        files: BTreeSet<PathBuf>,
+        dirs: BTreeSet<PathBuf>,
+        links: HashMap<PathBuf, PathBuf>,
+    }
+
+    impl FakeEnv {
+        fn new() -> Self {
+            let mut env = FakeEnv {
+                paths: LaunchPaths {
+                    party: PathBuf::from("/party"),
+                    steam: PathBuf::from("/steam"),
+                    res: PathBuf::from("/res"),
+                    umu_run: PathBuf::from("/bin/umu-run"),
+                    gsc_kbm: PathBuf::from("/bin/gamescope-kbm"),
+                },
+                gameroot: PathBuf::from("/games/Test"),
+                files: BTreeSet::new(),
+                dirs: BTreeSet::new(),
+                links: HashMap::new(),
+            };
+            env.links.insert("/steam/sdk32".into(), "/steam/linux32".into());
+            env.links.insert("/steam/sdk64".into(), "/steam/linux64".into());
+            env
+        }
+
+        fn file(mut self, path: &str) -> Self {
+            self.files.insert(path.into());
+            self
+        }
+
+        fn dir(mut self, path: &str) -> Self {
+            self.dirs.insert(path.into());
+            self
+        }
+    }
+
+    impl LaunchEnv for FakeEnv {
+        fn paths(&self) -> &LaunchPaths {
+            &self.paths
+        }
+
+        fn game_rootpath(&self, _h: &Handler) -> Result<PathBuf, Box<dyn Error>> {
+            Ok(self.gameroot.clone())
+        }
+
+        fn exists(&self, path: &Path) -> bool {
+            self.is_file(path) || self.is_dir(path)
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            self.files.contains(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            self.dirs.contains(path)
+        }
+
+        fn read_link(&self, path: &Path) -> std::io::Result<PathBuf> {
+            self.links
+                .get(path)
+                .cloned()
+                .ok_or_else(|| std::io::ErrorKind::NotFound.into())
+        }
+    }
+
+    fn device(name: &str, path: &str, device_type: DeviceType) -> DeviceInfo {
+        DeviceInfo {
+            id: DeviceId {
+                vendor: 0x045e,
+                product: 0x028e,
+                version: 0,
+                name: name.to_string(),
+                uniq: String::new(),
+                phys: path.to_string(),
+                link: String::new(),
+                index: 0,
+            },
+            path: path.to_string(),
+            nodes: Vec::new(),
+            enabled: true,
+            device_type,
+        }
+    }
+
+    fn instance(profile: &str, devices: &[&DeviceInfo]) -> Instance {
+        Instance {
+            devices: devices.iter().map(|dev| dev.id.clone()).collect(),
+            keymap: None,
+            profname: profile.to_string(),
+            profselection: 0,
+            monitor: 0,
+            width: 960,
+            height: 1080,
+            rect: Rect::FULL,
+        }
+    }
+
+    fn handler(exec: &str) -> Handler {
+        Handler {
+            name: "Test".to_string(),
+            path_gameroot: "/games/Test".to_string(),
+            exec: exec.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn config() -> PartyConfig {
+        PartyConfig {
+            kbm_support: false,
+            ..Default::default()
+        }
+    }
+
+    fn two_pads() -> Vec<DeviceInfo> {
+        vec![
+            device("Pad 1", "/dev/input/event10", DeviceType::Gamepad),
+            device("Pad 2", "/dev/input/event11", DeviceType::Gamepad),
+        ]
+    }
+
+    fn two_players(devices: &[DeviceInfo]) -> Vec<Instance> {
+        vec![
+            instance("Alice", &[&devices[0]]),
+            instance("Bob", &[&devices[1]]),
+        ]
+    }
+
+    // Compares the plan's JSON with tests/plans/<name>.json. Run with UPDATE_PLANS=1 to
+    // rewrite the expected plans after an intended change, and review the diff.
+    fn assert_plan(name: &str, plan: LaunchPlan) {
+        let mut plan = plan;
+        plan.partydeck_version = String::new();
+        let json = plan.to_json().unwrap() + "\n";
+        let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/plans")
+            .join(format!("{name}.json"));
+        if std::env::var_os("UPDATE_PLANS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, json).unwrap();
+            return;
+        }
+        let expected = std::fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("{} is missing; run with UPDATE_PLANS=1", path.display()));
+        assert_eq!(json, expected, "plan differs from {}", path.display());
+    }
+
+    fn build(
+        h: &Handler,
+        devices: &[DeviceInfo],
+        instances: &[Instance],
+        cfg: &PartyConfig,
+        env: &FakeEnv,
+    ) -> LaunchPlan {
+        build_launch_plan(h, devices, instances, cfg, &[], env, false).unwrap()
+    }
+
+    #[test]
+    fn native() {
+        let h = Handler {
+            args: "-profile $PROFILE -res $RESOLUTION".to_string(),
+            ..handler("bin/game.x86_64")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new().file("/games/Test/bin/game.x86_64");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("native", plan);
+    }
+
+    #[test]
+    fn proton() {
+        let h = handler("Game.exe");
+        let devices = two_pads();
+        let env = FakeEnv::new().file("/games/Test/Game.exe");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("proton", plan);
+    }
+
+    #[test]
+    fn proton_goldberg_mounted() {
+        let h = Handler {
+            path_handler: PathBuf::from("/party/handlers/test"),
+            use_goldberg: true,
+            steam_appid: Some(480),
+            ..handler("Game.exe")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new()
+            .file("/party/tmp/game-0/Game.exe")
+            .file("/party/tmp/game-1/Game.exe");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("proton_goldberg_mounted", plan);
+    }
+
+    #[test]
+    fn native_goldberg() {
+        let h = Handler {
+            use_goldberg: true,
+            steam_appid: Some(480),
+            ..handler("game")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new().file("/games/Test/game");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("native_goldberg", plan);
+    }
+
+    #[test]
+    fn scout_runtime() {
+        let h = Handler {
+            runtime: "scout".to_string(),
+            ..handler("game")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new()
+            .file("/games/Test/game")
+            .file("/steam/bin32/steam-runtime/run.sh");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("scout_runtime", plan);
+    }
+
+    #[test]
+    fn soldier_runtime() {
+        let h = Handler {
+            runtime: "soldier".to_string(),
+            ..handler("game")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new()
+            .file("/games/Test/game")
+            .dir("/steam/steam/steamapps/common/SteamLinuxRuntime_soldier");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("soldier_runtime", plan);
+    }
+
+    #[test]
+    fn missing_runtime_fails_launch() {
+        let h = Handler {
+            runtime: "soldier".to_string(),
+            ..handler("game")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new().file("/games/Test/game");
+        let instances = two_players(&devices);
+        let result = build_launch_plan(&h, &devices, &instances, &config(), &[], &env, false);
+        assert!(result.is_err());
+
+        let plan = build_launch_plan(&h, &devices, &instances, &config(), &[], &env, true).unwrap();
+        assert_eq!(plan.warnings, ["Steam Runtime soldier not found!"]);
+    }
+
+    #[test]
+    fn kbm() {
+        let h = handler("game");
+        let mut devices = two_pads();
+        devices.push(device("Keyboard", "/dev/input/event2", DeviceType::Keyboard));
+        devices.push(device("Mouse", "/dev/input/event3", DeviceType::Mouse));
+        let instances = vec![
+            instance("Alice", &[&devices[2], &devices[3]]),
+            instance("Bob", &[&devices[0]]),
+        ];
+        let cfg = PartyConfig {
+            kbm_support: true,
+            ..config()
+        };
+        let env = FakeEnv::new().file("/games/Test/game");
+        let plan = build(&h, &devices, &instances, &cfg, &env);
+        assert_plan("kbm", plan);
+    }
+
+    #[test]
+    fn game_null_paths() {
+        let h = Handler {
+            game_null_paths: vec!["intro.mp4".to_string(), "mods".to_string()],
+            ..handler("game")
+        };
+        let devices = two_pads();
+        let env = FakeEnv::new()
+            .file("/games/Test/game")
+            .file("/games/Test/intro.mp4")
+            .dir("/games/Test/mods");
+        let plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        assert_plan("game_null_paths", plan);
+    }
+
+    #[test]
+    fn non_utf8_args_reach_the_command() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let h = handler("bin/game.x86_64");
+        let devices = two_pads();
+        let env = FakeEnv::new().file("/games/Test/bin/game.x86_64");
+        let mut plan = build(&h, &devices, &two_players(&devices), &config(), &env);
+        let name = OsStr::from_bytes(b"/saves/caf\xe9");
+        plan.instances[0].arg(name).env("SAVE_DIR", name);
+
+        let cmd = plan.instances[0].command();
+        assert_eq!(cmd.get_args().last(), Some(name));
+        assert!(cmd.get_envs().any(|(key, value)| key == "SAVE_DIR" && value == Some(name)));
+        let json = plan.to_json().unwrap();
+        assert!(json.contains("\"/saves/caf\u{fffd}\""));
+    }
+}
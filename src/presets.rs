@@ -0,0 +1,157 @@
+use crate::app::PartyConfig;
+use crate::input::DeviceId;
+use crate::instance::Instance;
+use crate::layout::Rect;
+use crate::paths::PATH_PARTY;
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+// The setup of the last launch of each handler is kept as a preset with this name
+pub const LAST_SESSION_PRESET: &str = "Last session";
+
+// A saved set of players for one handler, so the same session can be set up again
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SessionPreset {
+    pub name: String,
+    pub handler: String,
+    pub layout: String,
+    pub players: Vec<PresetPlayer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PresetPlayer {
+    pub devices: Vec<DeviceId>,
+    // Empty for a guest
+    pub profile: String,
+    pub monitor: usize,
+    pub keymap: Option<usize>,
+}
+
+fn presets_path() -> PathBuf {
+    PATH_PARTY.join("presets.json")
+}
+
+pub fn load_presets() -> Vec<SessionPreset> {
+    let Ok(file) = File::open(presets_path()) else {
+        return Vec::new();
+    };
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(presets) => presets,
+        Err(err) => {
+            println!("[partydeck] Couldn't read session presets: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+fn save_presets(presets: &[SessionPreset]) -> Result<(), Box<dyn Error>> {
+    let file = File::create(presets_path())?;
+    serde_json::to_writer_pretty(file, presets)?;
+    Ok(())
+}
+
+// Adds the preset, replacing the handler's preset of the same name
+pub fn store_preset(
+    presets: &mut Vec<SessionPreset>,
+    preset: SessionPreset,
+) -> Result<(), Box<dyn Error>> {
+    presets.retain(|p| p.handler != preset.handler || p.name != preset.name);
+    presets.push(preset);
+    save_presets(presets)
+}
+
+pub fn delete_preset(presets: &mut Vec<SessionPreset>, i: usize) -> Result<(), Box<dyn Error>> {
+    presets.remove(i);
+    save_presets(presets)
+}
+
+// Keeps presets pointing at a profile after it's renamed
+pub fn rename_preset_profiles(
+    presets: &mut [SessionPreset],
+    old: &str,
+    new: &str,
+) -> Result<(), Box<dyn Error>> {
+    let players = presets
+        .iter_mut()
+        .flat_map(|preset| preset.players.iter_mut());
+    for player in players.filter(|player| player.profile == old) {
+        player.profile = new.to_string();
+    }
+    save_presets(presets)
+}
+
+// Players of a deleted profile join as guests instead
+pub fn forget_preset_profiles(
+    presets: &mut [SessionPreset],
+    profile: &str,
+) -> Result<(), Box<dyn Error>> {
+    let players = presets
+        .iter_mut()
+        .flat_map(|preset| preset.players.iter_mut());
+    for player in players.filter(|player| player.profile == profile) {
+        player.profile = String::new();
+    }
+    save_presets(presets)
+}
+
+impl SessionPreset {
+    // profiles is the profile selector's list, with the guest first
+    pub fn from_instances(
+        name: &str,
+        handler: &str,
+        instances: &[Instance],
+        profiles: &[String],
+        cfg: &PartyConfig,
+    ) -> Self {
+        SessionPreset {
+            name: name.to_string(),
+            handler: handler.to_string(),
+            layout: cfg.layout.clone(),
+            players: instances
+                .iter()
+                .map(|instance| PresetPlayer {
+                    devices: instance.devices.clone(),
+                    profile: match instance.profselection {
+                        0 => String::new(),
+                        i => profiles.get(i).cloned().unwrap_or_default(),
+                    },
+                    monitor: instance.monitor,
+                    keymap: instance.keymap,
+                })
+                .collect(),
+        }
+    }
+
+    // Profiles that no longer exist are replaced by guests, and missing monitors by the first one
+    pub fn to_instances(&self, profiles: &[String], monitors: usize) -> Vec<Instance> {
+        self.players
+            .iter()
+            .map(|player| Instance {
+                devices: player.devices.clone(),
+                keymap: player.keymap,
+                profname: String::new(),
+                profselection: match player.profile.is_empty() {
+                    true => 0,
+                    false => profiles
+                        .iter()
+                        .skip(1)
+                        .position(|p| *p == player.profile)
+                        .map_or(0, |i| i + 1),
+                },
+                monitor: match player.monitor < monitors {
+                    true => player.monitor,
+                    false => 0,
+                },
+                width: 0,
+                height: 0,
+                rect: Rect::FULL,
+            })
+            .collect()
+    }
+}
@@ -1,7 +1,95 @@
+use serde::{Deserialize, Serialize};
 use std::error::Error;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
 
-use crate::{handler::Handler, paths::*, util::copy_dir_recursive};
+use crate::{
+    handler::Handler, input::DeviceId, instance::Instance, paths::*, util::copy_dir_recursive,
+};
+
+// The profile a device was last used with, so it can be preselected when the device joins
+#[derive(Clone, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub device: DeviceId,
+    pub profile: String,
+}
+
+fn device_profiles_path() -> PathBuf {
+    PATH_PARTY.join("device_profiles.json")
+}
+
+pub fn load_device_profiles() -> Vec<DeviceProfile> {
+    let Ok(file) = File::open(device_profiles_path()) else {
+        return Vec::new();
+    };
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(mappings) => mappings,
+        Err(err) => {
+            println!("[partydeck] Couldn't read device profiles: {}", err);
+            Vec::new()
+        }
+    }
+}
+
+pub fn remembered_profile<'a>(mappings: &'a [DeviceProfile], device: &DeviceId) -> Option<&'a str> {
+    mappings
+        .iter()
+        .find(|mapping| &mapping.device == device)
+        .map(|mapping| mapping.profile.as_str())
+}
+
+// Remembers the profile each device was launched with; devices used as a guest are forgotten
+pub fn remember_device_profiles(
+    mappings: &mut Vec<DeviceProfile>,
+    instances: &[Instance],
+    profiles: &[String],
+) -> Result<(), Box<dyn Error>> {
+    for instance in instances {
+        for device in &instance.devices {
+            mappings.retain(|mapping| &mapping.device != device);
+            if instance.profselection != 0 {
+                mappings.push(DeviceProfile {
+                    device: device.clone(),
+                    profile: profiles[instance.profselection].clone(),
+                });
+            }
+        }
+    }
+    save_device_profiles(mappings)
+}
+
+// Keeps devices pointing at a profile after it's renamed
+pub fn rename_device_profiles(
+    mappings: &mut [DeviceProfile],
+    old: &str,
+    new: &str,
+) -> Result<(), Box<dyn Error>> {
+    for mapping in mappings.iter_mut().filter(|mapping| mapping.profile == old) {
+        mapping.profile = new.to_string();
+    }
+    save_device_profiles(mappings)
+}
+
+// Forgets the devices last used with a deleted profile
+pub fn forget_device_profiles(
+    mappings: &mut Vec<DeviceProfile>,
+    profile: &str,
+) -> Result<(), Box<dyn Error>> {
+    mappings.retain(|mapping| mapping.profile != profile);
+    save_device_profiles(mappings)
+}
+
+fn save_device_profiles(mappings: &[DeviceProfile]) -> Result<(), Box<dyn Error>> {
+    let file = File::create(device_profiles_path())?;
+    serde_json::to_writer_pretty(file, mappings)?;
+    Ok(())
+}
+
+// Profile names are used for directory names and Goldberg account names
+pub fn valid_profile_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(char::is_alphanumeric)
+}
 
 // Makes a folder and sets up Goldberg Steam Emu profile for Steam games
 pub fn create_profile(name: &str) -> Result<(), std::io::Error> {
@@ -30,6 +118,127 @@ pub fn create_profile(name: &str) -> Result<(), std::io::Error> {
     Ok(())
 }
 
+// Sets the name the profile goes by in games, in Goldberg's user settings
+fn set_account_name(path_prof: &Path, name: &str) -> Result<(), Box<dyn Error>> {
+    let path_settings = path_prof.join("steam/settings");
+    let path_ini = path_settings.join("configs.user.ini");
+    let account = format!("account_name={name}");
+
+    let contents = std::fs::read_to_string(&path_ini).unwrap_or_default();
+    let mut found = false;
+    let mut lines: Vec<String> = contents
+        .lines()
+        .map(|line| match line.trim_start().starts_with("account_name=") {
+            true => {
+                found = true;
+                account.clone()
+            }
+            false => line.to_string(),
+        })
+        .collect();
+    if !found {
+        match lines.iter().position(|line| line.trim() == "[user::general]") {
+            Some(i) => lines.insert(i + 1, account),
+            None => {
+                lines.push("[user::general]".to_string());
+                lines.push(account);
+            }
+        }
+    }
+
+    std::fs::create_dir_all(&path_settings)?;
+    std::fs::write(path_ini, lines.join("\n"))?;
+    Ok(())
+}
+
+pub fn rename_profile(old: &str, new: &str) -> Result<(), Box<dyn Error>> {
+    rename_profile_in(&PATH_PARTY.join("profiles"), old, new)
+}
+
+pub(crate) fn rename_profile_in(
+    path_profiles: &Path,
+    old: &str,
+    new: &str,
+) -> Result<(), Box<dyn Error>> {
+    if path_profiles.join(new).exists() {
+        return Err(format!("A profile named {new} already exists").into());
+    }
+    println!("[partydeck] Renaming profile {old} to {new}");
+    std::fs::rename(path_profiles.join(old), path_profiles.join(new))?;
+    set_account_name(&path_profiles.join(new), new)
+}
+
+// Copies everything in the profile, including its game saves. Save snapshots stay with the
+// original profile, as their history is its own.
+pub fn duplicate_profile(name: &str, new: &str) -> Result<(), Box<dyn Error>> {
+    let path_profiles = PATH_PARTY.join("profiles");
+    let path_new = path_profiles.join(new);
+    if path_new.exists() {
+        return Err(format!("A profile named {new} already exists").into());
+    }
+    println!("[partydeck] Duplicating profile {name} as {new}");
+    std::fs::create_dir_all(&path_new)?;
+    for entry in std::fs::read_dir(path_profiles.join(name))? {
+        let entry = entry?;
+        let dest = path_new.join(entry.file_name());
+        if entry.file_name() == "snapshots" {
+            continue;
+        }
+        if entry.file_type()?.is_dir() {
+            std::fs::create_dir_all(&dest)?;
+            copy_dir_recursive(&entry.path(), &dest)?;
+        } else {
+            std::fs::copy(entry.path(), dest)?;
+        }
+    }
+    set_account_name(&path_new, new)
+}
+
+pub fn delete_profile(name: &str) -> Result<(), Box<dyn Error>> {
+    println!("[partydeck] Deleting profile {name}");
+    std::fs::remove_dir_all(PATH_PARTY.join("profiles").join(name))?;
+    Ok(())
+}
+
+// Image formats Goldberg accepts for account avatars
+const AVATAR_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+pub fn profile_avatar(name: &str) -> Option<PathBuf> {
+    let path_prof = PATH_PARTY.join("profiles").join(name);
+    AVATAR_EXTENSIONS
+        .iter()
+        .map(|ext| path_prof.join(format!("avatar.{ext}")))
+        .find(|path| path.exists())
+}
+
+// Stores the avatar in the profile and gives it to Goldberg so it shows up in games
+pub fn set_profile_avatar(name: &str, image: &Path) -> Result<(), Box<dyn Error>> {
+    let ext = image
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+        .filter(|ext| AVATAR_EXTENSIONS.contains(&ext.as_str()))
+        .ok_or("Avatars must be PNG or JPEG images")?;
+
+    let path_prof = PATH_PARTY.join("profiles").join(name);
+    let path_settings = path_prof.join("steam/settings");
+    for old in AVATAR_EXTENSIONS {
+        for path in [
+            path_prof.join(format!("avatar.{old}")),
+            path_settings.join(format!("account_avatar.{old}")),
+        ] {
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+        }
+    }
+
+    std::fs::copy(image, path_prof.join(format!("avatar.{ext}")))?;
+    std::fs::create_dir_all(&path_settings)?;
+    std::fs::copy(image, path_settings.join(format!("account_avatar.{ext}")))?;
+    Ok(())
+}
+
 // Creates the "game save" folder for per-profile game data to go into
 pub fn create_profile_gamesave(name: &str, h: &Handler) -> Result<(), Box<dyn Error>> {
     let uid = h.handler_dir_name();
@@ -98,6 +307,28 @@ pub fn scan_profiles(include_guest: bool) -> Vec<String> {
     out
 }
 
+// Guest profiles left over from the last session, without the leading "."
+pub fn guest_profiles() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(PATH_PARTY.join("profiles")) else {
+        return Vec::new();
+    };
+    let mut out: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|ft| ft.is_dir()))
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            name.strip_prefix(".").map(str::to_string)
+        })
+        .collect();
+    out.sort();
+    out
+}
+
+// Turns a guest into a permanent profile, keeping its game saves, windata, home and Goldberg data
+pub fn promote_guest_profile(guest: &str, name: &str) -> Result<(), Box<dyn Error>> {
+    rename_profile(&format!(".{guest}"), name)
+}
+
 pub fn remove_guest_profiles() -> Result<(), Box<dyn Error>> {
     let path_profiles = PATH_PARTY.join("profiles");
     let entries = std::fs::read_dir(&path_profiles)?;
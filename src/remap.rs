@@ -0,0 +1,314 @@
+use crate::paths::PATH_PARTY;
+
+use evdev::{AbsInfo, AbsoluteAxisCode, EventSummary, EventType, InputEvent, KeyCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+// Gamepad buttons that can be remapped, by evdev name, with the names shown in the GUI
+pub const REMAP_BUTTONS: &[(&str, &str)] = &[
+    ("BTN_SOUTH", "A"),
+    ("BTN_EAST", "B"),
+    ("BTN_NORTH", "X"),
+    ("BTN_WEST", "Y"),
+    ("BTN_TL", "LB"),
+    ("BTN_TR", "RB"),
+    ("BTN_TL2", "LT (digital)"),
+    ("BTN_TR2", "RT (digital)"),
+    ("BTN_THUMBL", "Left stick click"),
+    ("BTN_THUMBR", "Right stick click"),
+    ("BTN_SELECT", "Select"),
+    ("BTN_START", "Start"),
+    ("BTN_MODE", "Guide"),
+    ("BTN_TRIGGER_HAPPY1", "Paddle 1"),
+    ("BTN_TRIGGER_HAPPY2", "Paddle 2"),
+    ("BTN_TRIGGER_HAPPY3", "Paddle 3"),
+    ("BTN_TRIGGER_HAPPY4", "Paddle 4"),
+    ("BTN_TRIGGER_HAPPY5", "Paddle 5"),
+    ("BTN_TRIGGER_HAPPY6", "Paddle 6"),
+    ("BTN_TRIGGER_HAPPY7", "Paddle 7"),
+    ("BTN_TRIGGER_HAPPY8", "Paddle 8"),
+];
+
+pub const REMAP_AXES: &[(&str, &str)] = &[
+    ("ABS_X", "Left stick X"),
+    ("ABS_Y", "Left stick Y"),
+    ("ABS_RX", "Right stick X"),
+    ("ABS_RY", "Right stick Y"),
+    ("ABS_Z", "Left trigger"),
+    ("ABS_RZ", "Right trigger"),
+];
+
+pub fn button_label(name: &str) -> &str {
+    REMAP_BUTTONS
+        .iter()
+        .find(|(btn, _)| *btn == name)
+        .map_or(name, |(_, label)| label)
+}
+
+// A profile's changes to its player's gamepad input. Only applied through virtual gamepads.
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct Remap {
+    // Button to the button it's sent as, e.g. "BTN_SOUTH": "BTN_EAST"
+    pub buttons: BTreeMap<String, String>,
+    pub inverted_axes: Vec<String>,
+    // Fraction of an axis's range around its center that's ignored
+    pub deadzones: BTreeMap<String, f32>,
+}
+
+fn remap_path(profile: &str) -> PathBuf {
+    PATH_PARTY.join("profiles").join(profile).join("remap.json")
+}
+
+pub fn load_remap(profile: &str) -> Remap {
+    let Ok(file) = File::open(remap_path(profile)) else {
+        return Remap::default();
+    };
+    match serde_json::from_reader(BufReader::new(file)) {
+        Ok(remap) => remap,
+        Err(err) => {
+            println!(
+                "[partydeck] Couldn't read {}'s button remapping: {}",
+                profile, err
+            );
+            Remap::default()
+        }
+    }
+}
+
+// An empty remapping removes the file, so only profiles that change something have one
+pub fn save_remap(profile: &str, remap: &Remap) -> Result<(), Box<dyn Error>> {
+    let path = remap_path(profile);
+    if remap.is_empty() {
+        if path.exists() {
+            std::fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+    let file = File::create(path)?;
+    serde_json::to_writer_pretty(file, remap)?;
+    Ok(())
+}
+
+impl Remap {
+    pub fn is_empty(&self) -> bool {
+        self.buttons.is_empty()
+            && self.inverted_axes.is_empty()
+            && self.deadzones.values().all(|dz| *dz <= 0.0)
+    }
+
+    // Ready to apply to a virtual gamepad with the given axes
+    pub fn for_pad(
+        &self,
+        axes: &[(AbsoluteAxisCode, AbsInfo)],
+    ) -> Result<PadRemap, Box<dyn Error>> {
+        let mut buttons: Vec<(KeyCode, KeyCode)> = Vec::new();
+        for (from, to) in &self.buttons {
+            let from = KeyCode::from_str(from).map_err(|_| format!("Unknown button {from}"))?;
+            let to = KeyCode::from_str(to).map_err(|_| format!("Unknown button {to}"))?;
+            buttons.push((from, to));
+        }
+
+        let mut pad_axes: Vec<AxisRemap> = Vec::new();
+        for (axis, info) in axes {
+            let name = format!("{:?}", axis);
+            let invert = self.inverted_axes.contains(&name);
+            let deadzone = self.deadzones.get(&name).copied().unwrap_or(0.0);
+            if invert || deadzone > 0.0 {
+                pad_axes.push(AxisRemap {
+                    axis: *axis,
+                    min: info.minimum(),
+                    max: info.maximum(),
+                    invert,
+                    deadzone: deadzone.clamp(0.0, 0.9),
+                });
+            }
+        }
+        Ok(PadRemap {
+            buttons,
+            axes: pad_axes,
+        })
+    }
+}
+
+struct AxisRemap {
+    axis: AbsoluteAxisCode,
+    min: i32,
+    max: i32,
+    invert: bool,
+    deadzone: f32,
+}
+
+impl AxisRemap {
+    fn apply(&self, value: i32) -> i32 {
+        let (min, max) = (self.min as f32, self.max as f32);
+        // Triggers rest at their minimum, sticks at their center
+        let rest = match self.axis {
+            AbsoluteAxisCode::ABS_Z | AbsoluteAxisCode::ABS_RZ if self.min >= 0 => min,
+            _ => (min + max) / 2.0,
+        };
+        let mut value = value as f32;
+
+        if self.deadzone > 0.0 {
+            let range = match value < rest {
+                true => rest - min,
+                false => max - rest,
+            };
+            let dist = (value - rest).abs();
+            let dead = range * self.deadzone;
+            value = match dist <= dead {
+                true => rest,
+                // Scaled so the axis still reaches its ends
+                false => rest + (value - rest).signum() * (dist - dead) / (1.0 - self.deadzone),
+            };
+        }
+        if self.invert {
+            value = max + min - value;
+        }
+        value.round().clamp(min, max) as i32
+    }
+}
+
+pub struct PadRemap {
+    buttons: Vec<(KeyCode, KeyCode)>,
+    axes: Vec<AxisRemap>,
+}
+
+impl PadRemap {
+    // Buttons the gamepad needs to have for the remapping to work
+    pub fn target_buttons(&self) -> Vec<KeyCode> {
+        self.buttons.iter().map(|(_, to)| *to).collect()
+    }
+
+    pub fn apply(&self, event: InputEvent) -> InputEvent {
+        match event.destructure() {
+            EventSummary::Key(_, key, value) => {
+                match self.buttons.iter().find(|(from, _)| *from == key) {
+                    Some((_, to)) => InputEvent::new(EventType::KEY.0, to.0, value),
+                    None => event,
+                }
+            }
+            EventSummary::AbsoluteAxis(_, axis, value) => {
+                match self.axes.iter().find(|a| a.axis == axis) {
+                    Some(remap) => {
+                        InputEvent::new(EventType::ABSOLUTE.0, axis.0, remap.apply(value))
+                    }
+                    None => event,
+                }
+            }
+            _ => event,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis(axis: AbsoluteAxisCode, min: i32, max: i32, invert: bool, deadzone: f32) -> AxisRemap {
+        AxisRemap {
+            axis,
+            min,
+            max,
+            invert,
+            deadzone,
+        }
+    }
+
+    fn key(key: KeyCode, value: i32) -> InputEvent {
+        InputEvent::new(EventType::KEY.0, key.0, value)
+    }
+
+    fn abs(axis: AbsoluteAxisCode, value: i32) -> InputEvent {
+        InputEvent::new(EventType::ABSOLUTE.0, axis.0, value)
+    }
+
+    fn summary(event: InputEvent) -> (u16, u16, i32) {
+        (event.event_type().0, event.code(), event.value())
+    }
+
+    #[test]
+    fn stick_deadzone_is_scaled() {
+        let stick = axis(AbsoluteAxisCode::ABS_X, -100, 100, false, 0.2);
+        assert_eq!(stick.apply(0), 0);
+        assert_eq!(stick.apply(15), 0);
+        assert_eq!(stick.apply(-20), 0);
+        // Past the deadzone, the rest of the range is stretched so the ends are still reached
+        assert_eq!(stick.apply(60), 50);
+        assert_eq!(stick.apply(-60), -50);
+        assert_eq!(stick.apply(100), 100);
+        assert_eq!(stick.apply(-100), -100);
+    }
+
+    #[test]
+    fn inverted_axes() {
+        let stick = axis(AbsoluteAxisCode::ABS_Y, -100, 100, true, 0.0);
+        assert_eq!(stick.apply(30), -30);
+        assert_eq!(stick.apply(-100), 100);
+
+        let trigger = axis(AbsoluteAxisCode::ABS_Z, 0, 255, true, 0.0);
+        assert_eq!(trigger.apply(0), 255);
+        assert_eq!(trigger.apply(255), 0);
+    }
+
+    #[test]
+    fn triggers_rest_at_their_minimum() {
+        let trigger = axis(AbsoluteAxisCode::ABS_RZ, 0, 255, false, 0.1);
+        assert_eq!(trigger.apply(0), 0);
+        assert_eq!(trigger.apply(25), 0);
+        assert_eq!(trigger.apply(130), 116);
+        assert_eq!(trigger.apply(255), 255);
+
+        // Triggers reported as centered axes rest in the middle like sticks
+        let centered = axis(AbsoluteAxisCode::ABS_Z, -100, 100, false, 0.1);
+        assert_eq!(centered.apply(5), 0);
+        assert_eq!(centered.apply(-100), -100);
+    }
+
+    #[test]
+    fn pad_remap_apply() {
+        let remap = Remap {
+            buttons: BTreeMap::from([("BTN_SOUTH".to_string(), "BTN_EAST".to_string())]),
+            inverted_axes: vec!["ABS_X".to_string()],
+            deadzones: BTreeMap::new(),
+        };
+        let axes = [
+            (AbsoluteAxisCode::ABS_X, AbsInfo::new(0, -100, 100, 0, 0, 0)),
+            (AbsoluteAxisCode::ABS_Y, AbsInfo::new(0, -100, 100, 0, 0, 0)),
+        ];
+        let pad = remap.for_pad(&axes).unwrap();
+        assert_eq!(pad.target_buttons(), [KeyCode::BTN_EAST]);
+
+        let apply = |event| summary(pad.apply(event));
+        assert_eq!(
+            apply(key(KeyCode::BTN_SOUTH, 1)),
+            summary(key(KeyCode::BTN_EAST, 1))
+        );
+        assert_eq!(
+            apply(key(KeyCode::BTN_NORTH, 1)),
+            summary(key(KeyCode::BTN_NORTH, 1))
+        );
+        assert_eq!(
+            apply(abs(AbsoluteAxisCode::ABS_X, 40)),
+            summary(abs(AbsoluteAxisCode::ABS_X, -40))
+        );
+        assert_eq!(
+            apply(abs(AbsoluteAxisCode::ABS_Y, 40)),
+            summary(abs(AbsoluteAxisCode::ABS_Y, 40))
+        );
+    }
+
+    #[test]
+    fn unknown_buttons_are_rejected() {
+        let remap = Remap {
+            buttons: BTreeMap::from([("BTN_SOUTH".to_string(), "BTN_NOPE".to_string())]),
+            ..Default::default()
+        };
+        assert!(remap.for_pad(&[]).is_err());
+    }
+}
@@ -0,0 +1,431 @@
+use crate::app::PartyConfig;
+use crate::input::*;
+use crate::logs::*;
+use crate::placement::*;
+use crate::plan::*;
+use crate::virtual_pads::VirtualPads;
+
+use nix::sys::signal::{Signal, kill};
+use nix::unistd::Pid;
+use std::error::Error;
+use std::os::unix::process::ExitStatusExt;
+use std::path::PathBuf;
+use std::process::{Child, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// How long instances get to quit after SIGTERM before they're killed
+const END_SESSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone, PartialEq)]
+pub enum InstanceStatus {
+    Starting,
+    Running,
+    Exited,
+    Crashed(String),
+    Failed(String),
+}
+
+impl InstanceStatus {
+    pub fn label(&self) -> String {
+        match self {
+            InstanceStatus::Starting => "Starting".to_string(),
+            InstanceStatus::Running => "Running".to_string(),
+            InstanceStatus::Exited => "Exited".to_string(),
+            InstanceStatus::Crashed(reason) => format!("Crashed ({reason})"),
+            InstanceStatus::Failed(reason) => format!("Failed to start ({reason})"),
+        }
+    }
+
+    fn from_exit(status: ExitStatus) -> Self {
+        if status.success() {
+            InstanceStatus::Exited
+        } else if let Some(code) = status.code() {
+            InstanceStatus::Crashed(format!("exit code {code}"))
+        } else if let Some(signal) = status.signal() {
+            InstanceStatus::Crashed(format!("signal {signal}"))
+        } else {
+            InstanceStatus::Crashed("unknown".to_string())
+        }
+    }
+}
+
+pub struct SessionInstance {
+    pub plan: InstancePlan,
+    pub child: Option<Child>,
+    pub pid: Option<u32>,
+    pub status: InstanceStatus,
+    pub restarts: u32,
+}
+
+impl SessionInstance {
+    pub fn is_running(&self) -> bool {
+        self.child.is_some()
+    }
+}
+
+#[derive(Default)]
+pub struct Session {
+    pub handler: String,
+    pub instances: Vec<SessionInstance>,
+    // Where instance output is written; output is inherited from PartyDeck if this is None
+    pub log_dir: Option<PathBuf>,
+    placer: Option<Box<dyn WindowPlacer>>,
+    // Kept alive for as long as the session runs, when virtual gamepads are enabled
+    pub virtual_pads: Option<VirtualPads>,
+    // Set when the user ends the session
+    pub end_requested: Option<Instant>,
+    // Every process that belonged to an instance when the session was ended
+    end_pids: Vec<i32>,
+    killed: bool,
+    // Set once the session has ended and been cleaned up
+    pub finished: bool,
+}
+
+pub type SharedSession = Arc<Mutex<Session>>;
+
+pub fn new_shared_session(handler: &str) -> SharedSession {
+    let log_dir = match new_session_log_dir() {
+        Ok(dir) => Some(dir),
+        Err(err) => {
+            println!("[partydeck] Failed to create session log directory: {}", err);
+            None
+        }
+    };
+    Arc::new(Mutex::new(Session {
+        handler: handler.to_string(),
+        log_dir,
+        ..Default::default()
+    }))
+}
+
+impl Session {
+    pub fn add_instance(&mut self, plan: InstancePlan) -> usize {
+        self.instances.push(SessionInstance {
+            plan,
+            child: None,
+            pid: None,
+            status: InstanceStatus::Starting,
+            restarts: 0,
+        });
+        self.instances.len() - 1
+    }
+
+    pub fn spawn(&mut self, i: usize) -> Result<(), Box<dyn Error>> {
+        let instance = &mut self.instances[i];
+        if instance.is_running() {
+            return Err(format!("Instance {} is already running", i + 1).into());
+        }
+
+        let mut cmd = instance.plan.command();
+        if self.log_dir.is_some() {
+            cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+        }
+
+        match cmd.spawn() {
+            Ok(mut child) => {
+                println!("[partydeck] Instance {} started (PID {})", i + 1, child.id());
+                if let Some(dir) = &self.log_dir {
+                    let log_path = dir.join(format!("instance-{}.log", i + 1));
+                    if let Err(err) = capture_output(&mut child, &log_path) {
+                        println!("[partydeck] Failed to open {}: {}", log_path.display(), err);
+                    }
+                }
+                instance.pid = Some(child.id());
+                instance.child = Some(child);
+                instance.status = InstanceStatus::Running;
+                Ok(())
+            }
+            Err(err) => {
+                instance.pid = None;
+                instance.status = InstanceStatus::Failed(err.to_string());
+                Err(err.into())
+            }
+        }
+    }
+
+    // Starts a stopped instance again with the same command, profile, devices and gamescope geometry
+    pub fn restart(&mut self, i: usize) -> Result<(), Box<dyn Error>> {
+        self.spawn(i)?;
+        self.instances[i].restarts += 1;
+        self.update_placement()
+    }
+
+    fn windows(&self) -> Vec<WindowPlacement> {
+        self.instances
+            .iter()
+            .filter(|instance| instance.is_running())
+            .filter_map(|instance| {
+                Some(WindowPlacement {
+                    pid: instance.pid?,
+                    monitor: instance.plan.monitor,
+                    rect: instance.plan.rect,
+                })
+            })
+            .collect()
+    }
+
+    // Errors are returned rather than shown, so the session can be unlocked before reporting them
+    pub fn start_placement(&mut self, placer: Box<dyn WindowPlacer>) -> Result<(), Box<dyn Error>> {
+        self.placer = Some(placer);
+        self.update_placement()
+    }
+
+    fn update_placement(&mut self) -> Result<(), Box<dyn Error>> {
+        let windows = self.windows();
+        if let Some(placer) = &mut self.placer
+            && let Err(err) = placer.place(&windows)
+        {
+            println!("[partydeck] {} window placement failed: {}", placer.name(), err);
+            return Err(format!("Window placement failed: {err}").into());
+        }
+        Ok(())
+    }
+
+    pub fn stop_placement(&mut self) {
+        if let Some(mut placer) = self.placer.take()
+            && let Err(err) = placer.stop()
+        {
+            println!("[partydeck] Failed to stop {} window placement: {}", placer.name(), err);
+        }
+    }
+
+    // Checks every running instance for exits without blocking
+    pub fn poll(&mut self) {
+        for (i, instance) in self.instances.iter_mut().enumerate() {
+            let Some(child) = &mut instance.child else {
+                continue;
+            };
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    instance.status = InstanceStatus::from_exit(status);
+                    println!(
+                        "[partydeck] Instance {} (PID {}): {}",
+                        i + 1,
+                        child.id(),
+                        instance.status.label()
+                    );
+                    instance.child = None;
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    println!("[partydeck] Failed to check instance {}: {}", i + 1, err);
+                }
+            }
+        }
+    }
+
+    pub fn running_count(&self) -> usize {
+        self.instances.iter().filter(|i| i.is_running()).count()
+    }
+
+    pub fn all_exited_cleanly(&self) -> bool {
+        self.instances
+            .iter()
+            .all(|i| i.status == InstanceStatus::Exited)
+    }
+
+    pub fn is_ending(&self) -> bool {
+        self.end_requested.is_some()
+    }
+
+    // Asks every instance's process tree to quit; whatever is still around after
+    // END_SESSION_TIMEOUT is killed by the supervisor
+    pub fn request_end(&mut self) {
+        if self.is_ending() {
+            return;
+        }
+        println!("[partydeck] Ending session");
+        self.end_requested = Some(Instant::now());
+        self.end_pids = self.instance_pids();
+        signal_pids(&self.end_pids, Signal::SIGTERM);
+    }
+
+    fn kill_remaining(&mut self) {
+        if self.killed {
+            return;
+        }
+        self.killed = true;
+        let mut pids = self.end_pids.clone();
+        for pid in self.instance_pids() {
+            if !pids.contains(&pid) {
+                pids.push(pid);
+            }
+        }
+        // Children that outlived their parents get reparented, so check each pid is still alive
+        pids.retain(|pid| std::path::Path::new(&format!("/proc/{pid}")).exists());
+        if !pids.is_empty() {
+            println!("[partydeck] Killing {} remaining processes", pids.len());
+            signal_pids(&pids, Signal::SIGKILL);
+        }
+    }
+
+    fn instance_pids(&self) -> Vec<i32> {
+        let mut pids = Vec::new();
+        for instance in &self.instances {
+            if let Some(child) = &instance.child {
+                pids.extend(process_tree(child.id() as i32));
+            }
+        }
+        pids
+    }
+}
+
+// Returns a process along with all of its descendants
+fn process_tree(root: i32) -> Vec<i32> {
+    let mut parents: Vec<(i32, i32)> = Vec::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse::<i32>().ok()) else {
+                continue;
+            };
+            let Ok(stat) = std::fs::read_to_string(entry.path().join("stat")) else {
+                continue;
+            };
+            // The process name can contain spaces, so the fields are read after its closing parenthesis
+            if let Some((_, rest)) = stat.rsplit_once(')')
+                && let Some(ppid) = rest.split_whitespace().nth(1)
+                && let Ok(ppid) = ppid.parse::<i32>()
+            {
+                parents.push((pid, ppid));
+            }
+        }
+    }
+
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        for (pid, ppid) in &parents {
+            if *ppid == parent && !tree.contains(pid) {
+                tree.push(*pid);
+            }
+        }
+        i += 1;
+    }
+    tree
+}
+
+fn signal_pids(pids: &[i32], signal: Signal) {
+    for pid in pids {
+        if let Err(err) = kill(Pid::from_raw(*pid), signal) {
+            println!("[partydeck] Failed to send {signal} to PID {pid}: {err}");
+        }
+    }
+}
+
+// Tracks how long the end session button combination has been held on any device
+struct ChordWatcher {
+    devices: Vec<InputDevice>,
+    chord: Vec<PadButton>,
+    hold_time: Duration,
+    held_since: Option<Instant>,
+}
+
+impl ChordWatcher {
+    // The physical controllers are grabbed when virtual gamepads are used, so the chord is
+    // read from the virtual gamepads instead
+    fn new(cfg: &PartyConfig, virtual_pads: Option<&[Vec<String>]>) -> Self {
+        let devices = match (cfg.end_session_chord.is_empty(), virtual_pads) {
+            (true, _) => Vec::new(),
+            (false, Some(nodes)) => nodes
+                .iter()
+                .flatten()
+                .filter(|node| node.starts_with("/dev/input/event"))
+                .filter_map(|node| open_input_path(node))
+                .collect(),
+            (false, None) => scan_input_devices(&cfg.pad_filter_type)
+                .into_iter()
+                .filter(|dev| dev.enabled() && dev.device_type().is_controller())
+                .collect(),
+        };
+        Self {
+            devices,
+            chord: cfg.end_session_chord.clone(),
+            hold_time: Duration::from_secs_f64(cfg.end_session_chord_secs.max(0.0)),
+            held_since: None,
+        }
+    }
+
+    // Returns true once the chord has been held down for long enough
+    fn triggered(&mut self) -> bool {
+        let mut held = false;
+        for dev in &mut self.devices {
+            while dev.poll().is_some() {}
+            if self.chord.iter().all(|btn| dev.is_held(*btn)) {
+                held = true;
+            }
+        }
+
+        match (held, self.held_since) {
+            (false, _) => self.held_since = None,
+            (true, None) => self.held_since = Some(Instant::now()),
+            (true, Some(since)) => return since.elapsed() >= self.hold_time,
+        }
+        false
+    }
+}
+
+// Ends a session that failed partway through launching: instances that did start are asked to
+// quit, killed if they don't in time, and the virtual gamepads are removed. Only locks the
+// session briefly, so it can be shown while this waits.
+pub fn abort_session(session: &SharedSession) {
+    session.lock().unwrap().request_end();
+    loop {
+        {
+            let mut session = session.lock().unwrap();
+            session.poll();
+            let timed_out = session
+                .end_requested
+                .is_some_and(|since| since.elapsed() >= END_SESSION_TIMEOUT);
+            if session.running_count() == 0 || timed_out {
+                session.kill_remaining();
+                session.stop_placement();
+                if let Some(mut pads) = session.virtual_pads.take() {
+                    pads.stop();
+                }
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+}
+
+// Watches the session's instances until it is over. When interactive, a session whose
+// instances crashed stays open so they can be restarted, until the user ends it.
+pub fn supervise(session: &SharedSession, cfg: &PartyConfig, interactive: bool) {
+    let mut chord = {
+        let session = session.lock().unwrap();
+        let virtual_pads = session.virtual_pads.as_ref().map(|pads| pads.nodes.as_slice());
+        ChordWatcher::new(cfg, virtual_pads)
+    };
+    loop {
+        let chord_triggered = chord.triggered();
+        {
+            let mut session = session.lock().unwrap();
+            if chord_triggered {
+                session.request_end();
+            }
+            session.poll();
+            if let Some(since) = session.end_requested
+                && since.elapsed() >= END_SESSION_TIMEOUT
+            {
+                session.kill_remaining();
+            }
+            if session.running_count() == 0
+                && (!interactive || session.is_ending() || session.all_exited_cleanly())
+            {
+                if session.is_ending() {
+                    session.kill_remaining();
+                }
+                session.stop_placement();
+                if let Some(mut pads) = session.virtual_pads.take() {
+                    pads.stop();
+                }
+                break;
+            }
+        }
+        std::thread::sleep(Duration::from_millis(100));
+    }
+    println!("[partydeck] Session ended");
+}
@@ -0,0 +1,292 @@
+use crate::paths::PATH_PARTY;
+
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+
+const SNAPSHOT_DATE_FORMAT: &str = "%Y-%m-%d_%H-%M-%S";
+
+// Profile data that goes into a snapshot, besides the game's own save directory.
+// These are shared by all of the profile's games; steam holds Goldberg's save data.
+const SHARED_DIRS: [&str; 3] = ["windata", "home", "steam"];
+
+// Goldberg's account name and avatar, which belong to the profile rather than its saves. They are
+// left out of snapshots, so restoring one taken before a rename doesn't bring back the old name.
+const PROFILE_SETTINGS: &str = "steam/settings";
+
+// A zipped copy of a profile's save data for one game, taken before a launch
+#[derive(Clone)]
+pub struct SaveSnapshot {
+    // Handler directory name
+    pub handler: String,
+    pub path: PathBuf,
+    pub date: String,
+}
+
+fn snapshots_dir(profile: &str) -> PathBuf {
+    PATH_PARTY.join("profiles").join(profile).join("snapshots")
+}
+
+// Returns the profile's snapshots, newest first
+pub fn scan_snapshots(profile: &str) -> Vec<SaveSnapshot> {
+    scan_snapshots_in(&snapshots_dir(profile))
+}
+
+fn scan_snapshots_in(dir: &Path) -> Vec<SaveSnapshot> {
+    let mut out: Vec<SaveSnapshot> = Vec::new();
+    let Ok(handlers) = std::fs::read_dir(dir) else {
+        return out;
+    };
+    for handler in handlers.flatten() {
+        let Ok(entries) = std::fs::read_dir(handler.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "zip") {
+                continue;
+            }
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let Ok(date) = chrono::NaiveDateTime::parse_from_str(&stem, SNAPSHOT_DATE_FORMAT)
+            else {
+                continue;
+            };
+            out.push(SaveSnapshot {
+                handler: handler.file_name().to_string_lossy().to_string(),
+                date: date.format("%Y-%m-%d %H:%M:%S").to_string(),
+                path,
+            });
+        }
+    }
+    out.sort_by(|a, b| b.date.cmp(&a.date));
+    out
+}
+
+// Zips up the profile's save data for the handler, then removes all but the newest `kept`
+// snapshots for it. Does nothing if the profile has never played the game.
+pub fn take_snapshot(profile: &str, handler: &str, kept: usize) -> Result<(), Box<dyn Error>> {
+    take_snapshot_at(&PATH_PARTY.join("profiles").join(profile), handler, kept)
+}
+
+fn take_snapshot_at(path_prof: &Path, handler: &str, kept: usize) -> Result<(), Box<dyn Error>> {
+    let gamesave = Path::new("gamesaves").join(handler);
+    if !path_prof.join(&gamesave).exists() {
+        return Ok(());
+    }
+
+    let dir = path_prof.join("snapshots").join(handler);
+    std::fs::create_dir_all(&dir)?;
+    let name = chrono::Local::now()
+        .format(SNAPSHOT_DATE_FORMAT)
+        .to_string();
+    let path = dir.join(format!("{name}.zip"));
+    println!("[partydeck] Taking save snapshot {}", path.display());
+
+    let mut zip = zip::ZipWriter::new(BufWriter::new(File::create(&path)?));
+    let mut dirs = vec![gamesave];
+    dirs.extend(SHARED_DIRS.iter().map(PathBuf::from));
+    for dir in dirs {
+        if let Err(err) = add_to_zip(&mut zip, path_prof, &dir) {
+            drop(zip);
+            let _ = std::fs::remove_file(&path);
+            return Err(err);
+        }
+    }
+    zip.finish()?;
+
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(&dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .collect();
+    snapshots.sort();
+    while snapshots.len() > kept.max(1) {
+        let oldest = snapshots.remove(0);
+        println!(
+            "[partydeck] Removing old save snapshot {}",
+            oldest.display()
+        );
+        std::fs::remove_file(oldest)?;
+    }
+    Ok(())
+}
+
+fn add_to_zip(
+    zip: &mut zip::ZipWriter<BufWriter<File>>,
+    root: &Path,
+    dir: &Path,
+) -> Result<(), Box<dyn Error>> {
+    if !root.join(dir).exists() {
+        return Ok(());
+    }
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+
+    let entries = walkdir::WalkDir::new(root.join(dir))
+        .follow_links(false)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != root.join(PROFILE_SETTINGS));
+    for entry in entries {
+        let entry = entry?;
+        let name = entry
+            .path()
+            .strip_prefix(root)?
+            .to_string_lossy()
+            .to_string();
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            zip.add_directory(name, options)?;
+        } else if file_type.is_symlink() {
+            let target = std::fs::read_link(entry.path())?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+        } else if file_type.is_file() {
+            let mode = entry.metadata()?.permissions().mode();
+            zip.start_file(name, options.unix_permissions(mode))?;
+            std::io::copy(&mut File::open(entry.path())?, zip)?;
+        }
+        // Anything else, like overlay whiteouts, can't be stored and is skipped
+    }
+    Ok(())
+}
+
+// Puts the snapshot's save data back. The snapshot is unpacked next to the profile first, so a
+// broken one leaves the current saves untouched. The game's save directory is then swapped for
+// the snapshot's. Files in the shared directories are replaced by the snapshot's copies, which
+// also rolls back other games' saves that were already there when it was taken; files created
+// since then are left alone.
+pub fn restore_snapshot(profile: &str, snapshot: &SaveSnapshot) -> Result<(), Box<dyn Error>> {
+    restore_snapshot_at(&PATH_PARTY.join("profiles").join(profile), snapshot)
+}
+
+fn restore_snapshot_at(path_prof: &Path, snapshot: &SaveSnapshot) -> Result<(), Box<dyn Error>> {
+    println!(
+        "[partydeck] Restoring save snapshot {}",
+        snapshot.path.display()
+    );
+
+    let path_tmp = path_prof.join("snapshots").join(".restoring");
+    if path_tmp.exists() {
+        std::fs::remove_dir_all(&path_tmp)?;
+    }
+    if let Err(err) = extract_snapshot(&snapshot.path, &path_tmp) {
+        let _ = std::fs::remove_dir_all(&path_tmp);
+        return Err(err);
+    }
+    // Snapshots taken by older versions still have the profile's settings in them
+    if path_tmp.join(PROFILE_SETTINGS).exists() {
+        std::fs::remove_dir_all(path_tmp.join(PROFILE_SETTINGS))?;
+    }
+
+    let gamesave = Path::new("gamesaves").join(&snapshot.handler);
+    swap_dir(&path_tmp.join(&gamesave), &path_prof.join(&gamesave))?;
+    for dir in SHARED_DIRS {
+        let src = path_tmp.join(dir);
+        if src.exists() {
+            move_files_over(&src, &path_prof.join(dir))?;
+        }
+    }
+
+    std::fs::remove_dir_all(&path_tmp)?;
+    Ok(())
+}
+
+fn extract_snapshot(path: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let mut archive = zip::ZipArchive::new(File::open(path)?)?;
+    archive.extract(dest)?;
+    Ok(())
+}
+
+// Replaces dest with src, putting dest back if that fails
+fn swap_dir(src: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let mut old = dest.as_os_str().to_owned();
+    old.push(".replaced");
+    let old = PathBuf::from(old);
+    if old.exists() {
+        std::fs::remove_dir_all(&old)?;
+    }
+    if dest.exists() {
+        std::fs::rename(dest, &old)?;
+    }
+
+    let moved = match src.exists() {
+        true => std::fs::rename(src, dest),
+        false => std::fs::create_dir_all(dest),
+    };
+    if let Err(err) = moved {
+        if old.exists() {
+            let _ = std::fs::remove_dir_all(dest);
+            let _ = std::fs::rename(&old, dest);
+        }
+        return Err(err.into());
+    }
+
+    if old.exists() {
+        std::fs::remove_dir_all(&old)?;
+    }
+    Ok(())
+}
+
+// Moves every file in src to the same place under dest, replacing what's there
+fn move_files_over(src: &Path, dest: &Path) -> Result<(), Box<dyn Error>> {
+    let entries: Vec<walkdir::DirEntry> = walkdir::WalkDir::new(src)
+        .follow_links(false)
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+    for entry in entries {
+        let path = dest.join(entry.path().strip_prefix(src)?);
+        if entry.file_type().is_dir() {
+            std::fs::create_dir_all(&path)?;
+        } else {
+            std::fs::rename(entry.path(), &path)?;
+        }
+    }
+    Ok(())
+}
+
+pub fn delete_snapshot(snapshot: &SaveSnapshot) -> Result<(), Box<dyn Error>> {
+    std::fs::remove_file(&snapshot.path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::rename_profile_in;
+
+    fn user_settings(path_prof: &Path) -> String {
+        std::fs::read_to_string(path_prof.join("steam/settings/configs.user.ini")).unwrap()
+    }
+
+    #[test]
+    fn restore_keeps_the_renamed_account() {
+        let path_profiles =
+            std::env::temp_dir().join(format!("partydeck-test-snapshots-{}", std::process::id()));
+        let old = path_profiles.join("Alice");
+        std::fs::create_dir_all(old.join("steam/settings")).unwrap();
+        std::fs::create_dir_all(old.join("gamesaves/game")).unwrap();
+        std::fs::write(
+            old.join("steam/settings/configs.user.ini"),
+            "[user::general]\naccount_name=Alice",
+        )
+        .unwrap();
+        std::fs::write(old.join("gamesaves/game/save.txt"), "old").unwrap();
+        take_snapshot_at(&old, "game", 5).unwrap();
+
+        rename_profile_in(&path_profiles, "Alice", "Bob").unwrap();
+        let new = path_profiles.join("Bob");
+        std::fs::write(new.join("gamesaves/game/save.txt"), "new").unwrap();
+        let snapshots = scan_snapshots_in(&new.join("snapshots"));
+        assert_eq!(snapshots.len(), 1);
+        restore_snapshot_at(&new, &snapshots[0]).unwrap();
+
+        let save = std::fs::read_to_string(new.join("gamesaves/game/save.txt")).unwrap();
+        let account = user_settings(&new);
+        std::fs::remove_dir_all(&path_profiles).unwrap();
+        assert_eq!(save, "old");
+        assert_eq!(account, "[user::general]\naccount_name=Bob");
+    }
+}
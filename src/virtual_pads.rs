@@ -0,0 +1,417 @@
+use crate::input::*;
+use crate::instance::Instance;
+use crate::keymap::*;
+use crate::remap::*;
+
+use evdev::uinput::VirtualDevice;
+use evdev::*;
+use nix::poll::{PollFd, PollFlags, PollTimeout, poll};
+use std::error::Error;
+use std::os::fd::AsFd;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+
+// Every virtual gamepad's name starts with this, so PartyDeck can leave them out of its device list
+pub const VIRTUAL_PAD_NAME: &str = "PartyDeck Virtual Gamepad";
+
+// One uinput gamepad per instance, fed by the physical controllers assigned to it, and by its
+// keyboard when that is used as a gamepad. These devices are grabbed so that nothing else
+// receives their input while the session runs.
+pub struct VirtualPads {
+    // Device nodes of each instance's virtual gamepad; empty for instances without controllers
+    pub nodes: Vec<Vec<String>>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl VirtualPads {
+    // keymaps are the ones instances' keyboard gamepads refer to; with none, keyboards are left
+    // alone. remaps holds each instance's profile's button remapping.
+    pub fn start(
+        input_devices: &[DeviceInfo],
+        instances: &[Instance],
+        keymaps: &[Keymap],
+        remaps: &[Remap],
+    ) -> Result<Self, Box<dyn Error>> {
+        let mut sources: Vec<Source> = Vec::new();
+        let mut virtuals: Vec<VirtualPad> = Vec::new();
+        let mut nodes: Vec<Vec<String>> = Vec::new();
+
+        for (i, instance) in instances.iter().enumerate() {
+            let keymap = match instance.keymap {
+                Some(k) if !keymaps.is_empty() => Some(keymaps.get(k).ok_or(format!(
+                    "Player {}: keymap {} doesn't exist",
+                    i + 1,
+                    k + 1
+                ))?),
+                _ => None,
+            };
+            let pads: Vec<&DeviceInfo> = input_devices
+                .iter()
+                .filter(|dev| dev.enabled && instance.devices.contains(&dev.id))
+                .filter(|dev| {
+                    dev.device_type.is_controller()
+                        || (keymap.is_some() && dev.device_type == DeviceType::Keyboard)
+                })
+                .collect();
+            if pads.is_empty() {
+                nodes.push(Vec::new());
+                continue;
+            }
+
+            let mut source_indices: Vec<usize> = Vec::new();
+            for pad in &pads {
+                let s = match sources.iter().position(|s| s.path == pad.path) {
+                    Some(s) => s,
+                    None => {
+                        let mut dev = Device::open(&pad.path)
+                            .map_err(|e| format!("Couldn't open {}: {}", pad.path, e))?;
+                        dev.grab()
+                            .map_err(|e| format!("Couldn't grab {}: {}", pad.path, e))?;
+                        dev.set_nonblocking(true)?;
+                        sources.push(Source {
+                            path: pad.path.clone(),
+                            dev,
+                            targets: Vec::new(),
+                        });
+                        sources.len() - 1
+                    }
+                };
+                let bindings = match (pad.device_type == DeviceType::Keyboard, keymap) {
+                    (true, Some(keymap)) => Some(keymap.bindings()?),
+                    _ => None,
+                };
+                sources[s].targets.push(Target {
+                    pad: virtuals.len(),
+                    bindings,
+                });
+                source_indices.push(s);
+            }
+
+            let name = format!("{} {}", VIRTUAL_PAD_NAME, i + 1);
+            let remap = remaps.get(i).filter(|remap| !remap.is_empty());
+            let mut virt =
+                build_virtual_pad(&name, &sources, &source_indices, virtuals.len(), remap)
+                    .map_err(|e| format!("Player {}: {}", i + 1, e))?;
+            nodes.push(virtual_pad_nodes(&mut virt.dev)?);
+            println!("[partydeck] Created {} ({})", name, nodes[i].join(", "));
+            virtuals.push(virt);
+        }
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread = match sources.is_empty() {
+            true => None,
+            false => {
+                let stop = stop.clone();
+                Some(std::thread::spawn(move || {
+                    forward_events(sources, virtuals, &stop)
+                }))
+            }
+        };
+
+        Ok(Self {
+            nodes,
+            stop,
+            thread,
+        })
+    }
+
+    // Destroys the virtual gamepads and gives the physical controllers back
+    pub fn stop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for VirtualPads {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+// A grabbed physical device, with the virtual gamepads it feeds
+struct Source {
+    path: String,
+    dev: Device,
+    targets: Vec<Target>,
+}
+
+struct Target {
+    pad: usize,
+    // Set when the source is a keyboard that's turned into gamepad input
+    bindings: Option<KeyBindings>,
+}
+
+struct VirtualPad {
+    dev: VirtualDevice,
+    remap: Option<PadRemap>,
+}
+
+// Creates a gamepad with the buttons and axes of all of its physical controllers. The first
+// controller's IDs are used so that games apply the right button mappings; a keyboard
+// gamepad presents itself as an Xbox 360 controller, whose layout games know.
+fn build_virtual_pad(
+    name: &str,
+    sources: &[Source],
+    source_indices: &[usize],
+    pad: usize,
+    remap: Option<&Remap>,
+) -> Result<VirtualPad, Box<dyn Error>> {
+    let target = |s: usize| sources[s].targets.iter().find(|t| t.pad == pad);
+    let input_id = match target(source_indices[0]).is_some_and(|t| t.bindings.is_some()) {
+        true => InputId::new(BusType::BUS_USB, 0x045e, 0x028e, 0x0110),
+        false => {
+            let id = sources[source_indices[0]].dev.input_id();
+            InputId::new(BusType::BUS_USB, id.vendor(), id.product(), id.version())
+        }
+    };
+
+    let mut keys = AttributeSet::<KeyCode>::new();
+    let mut axes: Vec<(AbsoluteAxisCode, AbsInfo)> = Vec::new();
+    for &s in source_indices {
+        let source_axes = match target(s).and_then(|t| t.bindings.as_ref()) {
+            Some(bindings) => {
+                for key in bindings.buttons() {
+                    keys.insert(key);
+                }
+                bindings.axes()
+            }
+            None => {
+                let dev = &sources[s].dev;
+                if let Some(supported) = dev.supported_keys() {
+                    for key in supported.iter() {
+                        keys.insert(key);
+                    }
+                }
+                dev.get_absinfo()?.collect()
+            }
+        };
+        for (axis, info) in source_axes {
+            if !axes.iter().any(|(a, _)| *a == axis) {
+                axes.push((axis, info));
+            }
+        }
+    }
+
+    let remap = match remap {
+        Some(remap) => Some(remap.for_pad(&axes)?),
+        None => None,
+    };
+    for key in remap.iter().flat_map(|remap| remap.target_buttons()) {
+        keys.insert(key);
+    }
+
+    let mut builder = VirtualDevice::builder()?
+        .name(name)
+        .input_id(input_id)
+        .with_keys(&keys)?;
+    for (axis, info) in axes {
+        builder = builder.with_absolute_axis(&UinputAbsSetup::new(axis, info))?;
+    }
+    Ok(VirtualPad {
+        dev: builder.build()?,
+        remap,
+    })
+}
+
+// The event and joystick device nodes the kernel created for a virtual gamepad
+fn virtual_pad_nodes(virt: &mut VirtualDevice) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut out: Vec<String> = Vec::new();
+    for entry in std::fs::read_dir(virt.get_syspath()?)?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("event") || name.starts_with("js") {
+            out.push(format!("/dev/input/{name}"));
+        }
+    }
+    if !out.iter().any(|node| node.starts_with("/dev/input/event")) {
+        return Err("Virtual gamepad has no event device".into());
+    }
+    out.sort();
+    Ok(out)
+}
+
+fn forward_events(mut sources: Vec<Source>, mut virtuals: Vec<VirtualPad>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let ready: Vec<bool> = {
+            let mut fds: Vec<PollFd> = sources
+                .iter()
+                .map(|source| PollFd::new(source.dev.as_fd(), PollFlags::POLLIN))
+                .collect();
+            if let Err(err) = poll(&mut fds, PollTimeout::from(100u16)) {
+                println!("[partydeck] Virtual gamepads: poll failed: {}", err);
+                break;
+            }
+            fds.iter()
+                .map(|fd| fd.revents().is_some_and(|r| !r.is_empty()))
+                .collect()
+        };
+
+        let mut lost: Vec<usize> = Vec::new();
+        for (s, source) in sources.iter_mut().enumerate() {
+            if !ready[s] {
+                continue;
+            }
+            let events: Vec<InputEvent> = match source.dev.fetch_events() {
+                Ok(events) => events
+                    .filter(|ev| {
+                        ev.event_type() == EventType::KEY || ev.event_type() == EventType::ABSOLUTE
+                    })
+                    .collect(),
+                Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => continue,
+                Err(err) => {
+                    println!("[partydeck] Virtual gamepads: lost a controller: {}", err);
+                    lost.push(s);
+                    continue;
+                }
+            };
+            if events.is_empty() {
+                continue;
+            }
+            for target in source.targets.iter_mut() {
+                let events: Vec<InputEvent> = match &mut target.bindings {
+                    Some(bindings) => events
+                        .iter()
+                        .filter_map(|ev| match ev.destructure() {
+                            EventSummary::Key(_, key, value) => {
+                                Some(bindings.translate(key, value))
+                            }
+                            _ => None,
+                        })
+                        .flatten()
+                        .collect(),
+                    None => events.clone(),
+                };
+                if events.is_empty() {
+                    continue;
+                }
+                let virt = &mut virtuals[target.pad];
+                let events: Vec<InputEvent> = match &virt.remap {
+                    Some(remap) => events.into_iter().map(|ev| remap.apply(ev)).collect(),
+                    None => events,
+                };
+                if let Err(err) = virt.dev.emit(&events) {
+                    println!(
+                        "[partydeck] Virtual gamepads: couldn't forward input: {}",
+                        err
+                    );
+                }
+            }
+        }
+        // Unplugged controllers stop being polled; their players keep their virtual gamepad
+        for s in lost.into_iter().rev() {
+            sources.remove(s);
+        }
+    }
+    // Dropping the devices ungrabs the controllers and removes the virtual gamepads
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::Rect;
+    use std::time::{Duration, Instant};
+
+    // A uinput gamepad standing in for a physical controller
+    fn source_pad(name: &str) -> (VirtualDevice, DeviceInfo) {
+        let mut keys = AttributeSet::<KeyCode>::new();
+        keys.insert(KeyCode::BTN_SOUTH);
+        keys.insert(KeyCode::BTN_EAST);
+        let mut dev = VirtualDevice::builder()
+            .unwrap()
+            .name(name)
+            .input_id(InputId::new(BusType::BUS_USB, 0x1234, 0x5678, 1))
+            .with_keys(&keys)
+            .unwrap()
+            .with_absolute_axis(&UinputAbsSetup::new(
+                AbsoluteAxisCode::ABS_X,
+                AbsInfo::new(0, -32768, 32767, 16, 128, 0),
+            ))
+            .unwrap()
+            .build()
+            .unwrap();
+        let path = virtual_pad_nodes(&mut dev)
+            .unwrap()
+            .into_iter()
+            .find(|node| node.starts_with("/dev/input/event"))
+            .unwrap();
+        let info = DeviceInfo {
+            id: DeviceId {
+                vendor: 0x1234,
+                product: 0x5678,
+                version: 1,
+                name: name.to_string(),
+                uniq: String::new(),
+                phys: path.clone(),
+                link: String::new(),
+                index: 0,
+            },
+            path,
+            nodes: Vec::new(),
+            enabled: true,
+            device_type: DeviceType::Gamepad,
+        };
+        (dev, info)
+    }
+
+    fn open_pad(nodes: &[String]) -> Device {
+        let node = nodes
+            .iter()
+            .find(|node| node.starts_with("/dev/input/event"))
+            .unwrap();
+        let dev = Device::open(node).unwrap();
+        dev.set_nonblocking(true).unwrap();
+        dev
+    }
+
+    // Key events read from the virtual gamepad within the timeout
+    fn read_keys(dev: &mut Device, timeout: Duration) -> Vec<(KeyCode, i32)> {
+        let mut keys: Vec<(KeyCode, i32)> = Vec::new();
+        let start = Instant::now();
+        while keys.is_empty() && start.elapsed() < timeout {
+            if let Ok(events) = dev.fetch_events() {
+                keys.extend(events.filter_map(|ev| match ev.destructure() {
+                    EventSummary::Key(_, key, value) => Some((key, value)),
+                    _ => None,
+                }));
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        keys
+    }
+
+    #[test]
+    #[ignore = "needs write access to /dev/uinput; run with --ignored"]
+    fn events_reach_only_the_assigned_pad() {
+        let (mut pad_a, info_a) = source_pad("PartyDeck Test Pad A");
+        let (_pad_b, info_b) = source_pad("PartyDeck Test Pad B");
+        let devices = vec![info_a, info_b];
+        let instances: Vec<Instance> = devices
+            .iter()
+            .map(|dev| Instance {
+                devices: vec![dev.id.clone()],
+                keymap: None,
+                profname: String::new(),
+                profselection: 0,
+                monitor: 0,
+                width: 0,
+                height: 0,
+                rect: Rect::FULL,
+            })
+            .collect();
+
+        let mut pads = VirtualPads::start(&devices, &instances, &[], &[]).unwrap();
+        let mut virtual_a = open_pad(&pads.nodes[0]);
+        let mut virtual_b = open_pad(&pads.nodes[1]);
+
+        let press = InputEvent::new(EventType::KEY.0, KeyCode::BTN_SOUTH.0, 1);
+        pad_a.emit(&[press]).unwrap();
+
+        let keys = read_keys(&mut virtual_a, Duration::from_secs(2));
+        assert_eq!(keys, [(KeyCode::BTN_SOUTH, 1)]);
+        assert!(read_keys(&mut virtual_b, Duration::from_millis(300)).is_empty());
+        pads.stop();
+    }
+}